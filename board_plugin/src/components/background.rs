@@ -0,0 +1,5 @@
+use bevy::prelude::Component;
+
+/// Sprite drawn behind the tiles of the board
+#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Component)]
+pub struct BoardBackground;
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display, Formatter};
 use std::ops::{Add, Sub};
 
@@ -10,7 +11,20 @@ use std::ops::{Add, Sub};
 //     derive(Reflect, InspectorOptions),
 //     reflect(InspectorOptions)
 // )]
-#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component)]
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Hash,
+    Component,
+    Deserialize,
+    Serialize,
+)]
 pub struct Coordinates {
     pub x: u16,
     pub y: u16,
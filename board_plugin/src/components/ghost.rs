@@ -0,0 +1,20 @@
+use crate::components::Coordinates;
+use crate::TileMark;
+use bevy::prelude::Component;
+
+/// Copy of the tile at `source` displayed across the edge of a wrapping board
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+pub struct GhostTile {
+    pub source: Coordinates,
+    /// State of the source tile last displayed, `None` until first displayed
+    pub shown: Option<GhostState>,
+}
+
+/// State of a source tile mirrored by its ghost tiles
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GhostState {
+    /// The bombs are placed and the tile content can be shown
+    pub generated: bool,
+    pub covered: bool,
+    pub mark: Option<TileMark>,
+}
@@ -0,0 +1,13 @@
+use bevy::prelude::Component;
+
+/// Root node of the board HUD
+#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Component)]
+pub struct Hud;
+
+/// HUD text displaying the `MineCounter`
+#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Component)]
+pub struct MineCounterText;
+
+/// HUD text displaying the `GameClock`
+#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Component)]
+pub struct ClockText;
@@ -0,0 +1,5 @@
+use bevy::prelude::Component;
+
+/// Root node of the leaderboard screen
+#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Component)]
+pub struct LeaderboardScreen;
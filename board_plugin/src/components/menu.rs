@@ -0,0 +1,33 @@
+use crate::resources::Difficulty;
+use bevy::prelude::Component;
+
+/// Root node of the new game menu
+#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Component)]
+pub struct MenuScreen;
+
+/// Custom difficulty value edited in the new game menu
+#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub enum CustomField {
+    Width,
+    Height,
+    Bombs,
+}
+
+/// Action of a new game menu button
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+pub enum MenuButton {
+    /// Starts a game with the given difficulty
+    Preset(Difficulty),
+    /// Adds the given amount to a custom difficulty value
+    Adjust(CustomField, i32),
+    /// Starts a game with the custom difficulty, if valid
+    StartCustom,
+}
+
+/// New game menu text following the custom difficulty
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Component)]
+pub enum MenuText {
+    Field(CustomField),
+    /// Why the custom difficulty is invalid
+    Error,
+}
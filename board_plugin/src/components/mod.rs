@@ -1,9 +1,23 @@
+pub use background::BoardBackground;
 pub use bomb::Bomb;
 pub use coordinates::Coordinates;
+pub use ghost::{GhostState, GhostTile};
+pub use hud::{ClockText, Hud, MineCounterText};
+pub use leaderboard_screen::LeaderboardScreen;
+pub use menu::{CustomField, MenuButton, MenuScreen, MenuText};
 pub use neighbor::Neighbor;
+pub use replay_cursor::ReplayCursor;
+pub use stats_screen::StatsScreen;
 pub use uncover::{Mark, Uncover};
 
+mod background;
 mod bomb;
 mod coordinates;
+mod ghost;
+mod hud;
+mod leaderboard_screen;
+mod menu;
 mod neighbor;
+mod replay_cursor;
+mod stats_screen;
 mod uncover;
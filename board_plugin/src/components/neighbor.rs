@@ -2,5 +2,6 @@ use bevy::prelude::Component;
 
 #[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Component)]
 pub struct Neighbor {
-    pub count: u8,
+    /// Sum of the neighboring mines, negative mines subtracting from it
+    pub count: i8,
 }
@@ -0,0 +1,5 @@
+use bevy::prelude::Component;
+
+/// Marker of the recorded cursor position while playing a replay
+#[derive(Debug, Copy, Clone, Component)]
+pub struct ReplayCursor;
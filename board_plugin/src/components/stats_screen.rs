@@ -0,0 +1,5 @@
+use bevy::prelude::Component;
+
+/// Root node of the player stats screen
+#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Component)]
+pub struct StatsScreen;
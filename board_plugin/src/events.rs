@@ -1,5 +1,9 @@
 use crate::components::Coordinates;
+use crate::metrics::BoardMetrics;
+use crate::resources::{BoardError, ClickCounts, Difficulty};
 use bevy::prelude::Event;
+use bevy::utils::Duration;
+use std::path::PathBuf;
 
 #[derive(Debug, Copy, Clone, Event)]
 pub struct TileTriggerEvent(pub Coordinates);
@@ -7,8 +11,78 @@ pub struct TileTriggerEvent(pub Coordinates);
 #[derive(Debug, Copy, Clone, Event)]
 pub struct TileMarkEvent(pub Coordinates);
 
+/// Uncovers the unflagged neighbors of an uncovered counter once its flags match
 #[derive(Debug, Copy, Clone, Event)]
-pub struct BoardCompletedEvent;
+pub struct TileChordEvent(pub Coordinates);
 
+/// Every safe tile was uncovered, with the stats of the game
 #[derive(Debug, Copy, Clone, Event)]
-pub struct BombExplosionEvent;
+pub struct BoardCompletedEvent {
+    /// Time since the first uncovered tile
+    pub elapsed: Duration,
+    pub clicks: ClickCounts,
+    /// Difficulty metrics of the board
+    pub metrics: BoardMetrics,
+    pub seed: u64,
+}
+
+impl BoardCompletedEvent {
+    /// Efficiency of the game, in 3BV cleared per second
+    pub fn three_bv_per_second(&self) -> f32 {
+        self.metrics.three_bv as f32 / self.elapsed.as_secs_f32().max(f32::EPSILON)
+    }
+}
+
+/// The board options were refused or its mask couldn't be read, no board was created
+#[derive(Debug, Event)]
+pub struct BoardErrorEvent(pub BoardError);
+
+/// A bomb was uncovered at the given coordinates, ending the game
+#[derive(Debug, Copy, Clone, Event)]
+pub struct BombExplosionEvent(pub Coordinates);
+
+/// Writes the current game to the given save file
+#[derive(Debug, Clone, Event)]
+pub struct SaveGameEvent(pub PathBuf);
+
+/// Replaces the current game with the given save file
+#[derive(Debug, Clone, Event)]
+pub struct LoadGameEvent(pub PathBuf);
+
+/// Writes the inputs recorded on the current board to the given replay file
+#[derive(Debug, Clone, Event)]
+pub struct SaveReplayEvent(pub PathBuf);
+
+/// Recreates the board from the given replay file and plays its inputs back
+#[derive(Debug, Clone, Event)]
+pub struct PlayReplayEvent(pub PathBuf);
+
+/// Stops the replay playback, giving the board back to the player
+#[derive(Debug, Copy, Clone, Event)]
+pub struct StopReplayEvent;
+
+/// Controls the replay playback
+#[derive(Debug, Copy, Clone, Event)]
+pub enum ReplayControlEvent {
+    TogglePause,
+    /// Moves to the given time since the board was created
+    Seek(Duration),
+    /// Sets the playback speed, 1 being the recorded speed
+    Speed(f32),
+}
+
+/// Starts a new game with the given difficulty from the new game menu
+#[derive(Debug, Copy, Clone, Event)]
+pub struct NewGameEvent(pub Difficulty);
+
+/// Writes the `PlayerStats` to the given file as JSON
+#[derive(Debug, Clone, Event)]
+pub struct ExportStatsEvent(pub PathBuf);
+
+/// Reverts the last reveal or mark, when `BoardOptions::history` is enabled
+#[derive(Debug, Copy, Clone, Event)]
+pub struct UndoEvent;
+
+/// Applies the last reverted reveal or mark again
+#[derive(Debug, Copy, Clone, Event)]
+pub struct RedoEvent;
@@ -0,0 +1,276 @@
+use crate::resources::{
+    Board, BoardAssets, BoardFiles, BoardOptions, BoardTheme, ChosenDifficulty, DifficultySettings,
+    SpriteMaterial,
+};
+use crate::BoardPlugin;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::log;
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use std::fmt::{self, Display, Formatter};
+
+/// Loads `.board.ron` option files and `.theme.ron` theme files, and applies
+/// the files of the `BoardFiles` resource inserted by the host
+///
+/// Editing an option file regenerates the board, editing a theme file
+/// re-skins it keeping the game going. Needs the bevy `file_watcher` feature
+/// to follow the edits
+pub struct BoardFilesPlugin<T> {
+    pub start_state: T,
+}
+
+impl<T: States> Plugin for BoardFilesPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChosenDifficulty>()
+            .init_asset::<BoardOptions>()
+            .init_asset::<BoardTheme>()
+            .register_asset_loader(BoardOptionsLoader)
+            .register_asset_loader(BoardThemeLoader)
+            .add_systems(
+                Update,
+                (
+                    (Self::apply_options, Self::apply_theme)
+                        .run_if(resource_exists::<BoardFiles>()),
+                    (
+                        (BoardPlugin::<T>::reskin_board, BoardPlugin::<T>::reskin_hud)
+                            .run_if(Self::rebuild_is(Rebuild::Reskin))
+                            .run_if(resource_exists::<Board>()),
+                        BoardPlugin::enter(self.start_state.clone())
+                            .run_if(Self::rebuild_is(Rebuild::Regenerate)),
+                        Self::rebuild_done,
+                    )
+                        .chain()
+                        .run_if(resource_exists::<Rebuild>()),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Board rebuild requested by an edited file
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Resource)]
+enum Rebuild {
+    /// Updates the sprites of the current board with the new assets,
+    /// keeping the game and the host state
+    Reskin,
+    /// Generates a new board from the new options
+    Regenerate,
+}
+
+impl<T: States> BoardFilesPlugin<T> {
+    /// Replaces the `BoardOptions` resource with the loaded option file, the
+    /// difficulty picked in the menu, or else the saved one, keeps overriding
+    /// the board size
+    fn apply_options(
+        mut commands: Commands,
+        files: Res<BoardFiles>,
+        options: Res<Assets<BoardOptions>>,
+        chosen: Res<ChosenDifficulty>,
+        settings: Option<Res<DifficultySettings>>,
+        board: Option<Res<Board>>,
+        mut asset_rdr: EventReader<AssetEvent<BoardOptions>>,
+    ) {
+        let Some(handle) = &files.options else {
+            return;
+        };
+        let mut modified = false;
+        let mut loaded = false;
+        for event in asset_rdr.read() {
+            modified |= event.is_modified(handle);
+            loaded |= event.is_loaded_with_dependencies(handle) || event.is_modified(handle);
+        }
+        let (true, Some(options)) = (loaded, options.get(handle)) else {
+            return;
+        };
+
+        log::info!("Applying board options file");
+        let mut options = options.clone();
+        let difficulty = chosen
+            .0
+            .or_else(|| settings.map(|settings| settings.difficulty));
+        if let Some(difficulty) = difficulty {
+            difficulty.apply(&mut options);
+        }
+        commands.insert_resource(options);
+        if modified && board.is_some() {
+            commands.insert_resource(Rebuild::Regenerate);
+        }
+    }
+
+    /// Replaces the `BoardAssets` resource with the loaded theme file
+    fn apply_theme(
+        mut commands: Commands,
+        files: Res<BoardFiles>,
+        themes: Res<Assets<BoardTheme>>,
+        board: Option<Res<Board>>,
+        rebuild: Option<Res<Rebuild>>,
+        mut asset_rdr: EventReader<AssetEvent<BoardTheme>>,
+    ) {
+        let Some(handle) = &files.theme else {
+            return;
+        };
+        let mut modified = false;
+        let mut loaded = false;
+        for event in asset_rdr.read() {
+            modified |= event.is_modified(handle);
+            loaded |= event.is_loaded_with_dependencies(handle) || event.is_modified(handle);
+        }
+        let (true, Some(theme)) = (loaded, themes.get(handle)) else {
+            return;
+        };
+
+        log::info!("Applying board theme {}", theme.assets.label);
+        commands.insert_resource(theme.assets.clone());
+        // regenerating the board already applies the new assets
+        if modified && board.is_some() && rebuild.is_none() {
+            commands.insert_resource(Rebuild::Reskin);
+        }
+    }
+
+    /// Run condition checking the requested rebuild is `kind`
+    fn rebuild_is(kind: Rebuild) -> impl FnMut(Option<Res<Rebuild>>) -> bool {
+        move |rebuild| rebuild.is_some_and(|rebuild| *rebuild == kind)
+    }
+
+    fn rebuild_done(mut commands: Commands) {
+        commands.remove_resource::<Rebuild>();
+    }
+}
+
+/// Failure to load a board option or theme file
+#[derive(Debug)]
+pub enum BoardFileError {
+    Io(std::io::Error),
+    Deserialize(ron::error::SpannedError),
+}
+
+impl Display for BoardFileError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "board file access failed: {e}"),
+            Self::Deserialize(e) => write!(f, "invalid board file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BoardFileError {}
+
+/// Reads the file of `reader` as RON
+async fn read_ron<D: for<'de> Deserialize<'de>>(
+    reader: &mut Reader<'_>,
+) -> Result<D, BoardFileError> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(BoardFileError::Io)?;
+    ron::de::from_bytes(&bytes).map_err(BoardFileError::Deserialize)
+}
+
+/// Loads `BoardOptions` from `.board.ron` files
+#[derive(Debug, Default)]
+pub struct BoardOptionsLoader;
+
+impl AssetLoader for BoardOptionsLoader {
+    type Asset = BoardOptions;
+    type Settings = ();
+    type Error = BoardFileError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<BoardOptions, BoardFileError>> {
+        Box::pin(async move { read_ron(reader).await })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["board.ron"]
+    }
+}
+
+/// Sprite material of a theme file, with the asset path of its texture
+#[derive(Debug, Clone, Deserialize)]
+struct ThemeMaterial {
+    color: Color,
+    #[serde(default)]
+    texture: Option<String>,
+}
+
+/// Content of a `.theme.ron` file, describing `BoardAssets` by asset paths
+#[derive(Debug, Clone, Deserialize)]
+struct ThemeFile {
+    label: String,
+    board_material: ThemeMaterial,
+    tile_material: ThemeMaterial,
+    covered_tile_material: ThemeMaterial,
+    bomb_counter_font: String,
+    #[serde(default = "BoardAssets::default_colors")]
+    bomb_counter_colors: Vec<Color>,
+    flag_material: ThemeMaterial,
+    anti_flag_material: ThemeMaterial,
+    wrong_flag_material: ThemeMaterial,
+    question_material: ThemeMaterial,
+    bomb_material: ThemeMaterial,
+    anti_bomb_material: ThemeMaterial,
+    negative_counter_color: Color,
+    #[serde(default)]
+    pointy_hex_texture: Option<String>,
+    #[serde(default)]
+    flat_hex_texture: Option<String>,
+}
+
+/// Loads `BoardTheme` from `.theme.ron` files, along the textures and font
+/// they refer to
+#[derive(Debug, Default)]
+pub struct BoardThemeLoader;
+
+impl AssetLoader for BoardThemeLoader {
+    type Asset = BoardTheme;
+    type Settings = ();
+    type Error = BoardFileError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<BoardTheme, BoardFileError>> {
+        Box::pin(async move {
+            let file: ThemeFile = read_ron(reader).await?;
+            let mut material = |material: ThemeMaterial| SpriteMaterial {
+                color: material.color,
+                texture: material
+                    .texture
+                    .map(|path| load_context.load(path))
+                    .unwrap_or_default(),
+            };
+            let assets = BoardAssets {
+                label: file.label,
+                board_material: material(file.board_material),
+                tile_material: material(file.tile_material),
+                covered_tile_material: material(file.covered_tile_material),
+                bomb_counter_colors: file.bomb_counter_colors,
+                flag_material: material(file.flag_material),
+                anti_flag_material: material(file.anti_flag_material),
+                wrong_flag_material: material(file.wrong_flag_material),
+                question_material: material(file.question_material),
+                bomb_material: material(file.bomb_material),
+                anti_bomb_material: material(file.anti_bomb_material),
+                negative_counter_color: file.negative_counter_color,
+                bomb_counter_font: load_context.load(file.bomb_counter_font),
+                pointy_hex_texture: file.pointy_hex_texture.map(|path| load_context.load(path)),
+                flat_hex_texture: file.flat_hex_texture.map(|path| load_context.load(path)),
+            };
+            Ok(BoardTheme { assets })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
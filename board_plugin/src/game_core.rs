@@ -0,0 +1,425 @@
+use crate::components::Coordinates;
+use crate::resources::tile::Tile;
+use crate::resources::tile_map::TileMap;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// State of a game
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GameStatus {
+    Playing,
+    Won,
+    /// The bomb at the given coordinates was uncovered
+    Lost(Coordinates),
+}
+
+/// Player mark on a covered tile
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum TileMark {
+    /// The tile is believed to hide the given number of mines, it can't be uncovered
+    Flag(u8),
+    /// The tile is believed to hide the given number of negative mines, it can't be uncovered
+    AntiFlag(u8),
+    /// The player is unsure, the tile can still be uncovered
+    Question,
+}
+
+impl TileMark {
+    /// Number of mines flagged by the mark, regardless of their sign
+    pub fn flag_count(&self) -> u8 {
+        match self {
+            Self::Flag(count) | Self::AntiFlag(count) => *count,
+            Self::Question => 0,
+        }
+    }
+
+    /// Contribution of the mark to the counters around it, negative for
+    /// negative mines
+    pub fn value(&self) -> i32 {
+        match self {
+            Self::Flag(count) => *count as i32,
+            Self::AntiFlag(count) => -(*count as i32),
+            Self::Question => 0,
+        }
+    }
+}
+
+/// Minesweeper rules over a `TileMap`, independent from Bevy
+#[derive(Debug, Clone)]
+pub struct GameCore {
+    tile_map: TileMap,
+    covered: HashSet<Coordinates>,
+    /// Marks are kept when a tile is uncovered so undoing the reveal restores them
+    marks: HashMap<Coordinates, TileMark>,
+    status: GameStatus,
+}
+
+impl GameCore {
+    /// Starts a game with every tile of `tile_map` covered
+    pub fn new(tile_map: TileMap) -> Self {
+        let covered = (0..tile_map.height())
+            .flat_map(|y| (0..tile_map.width()).map(move |x| Coordinates { x, y }))
+            .filter(|c| !tile_map.is_hole(*c))
+            .collect();
+        Self {
+            tile_map,
+            covered,
+            marks: HashMap::new(),
+            status: GameStatus::Playing,
+        }
+    }
+
+    /// Restores a game from its uncovered and marked tiles
+    pub fn restore(
+        tile_map: TileMap,
+        revealed: &[Coordinates],
+        marks: impl IntoIterator<Item = (Coordinates, TileMark)>,
+    ) -> Self {
+        let mut core = Self::new(tile_map);
+        for coords in revealed {
+            core.covered.remove(coords);
+        }
+        core.marks = marks.into_iter().collect();
+        core.update_status();
+        core
+    }
+
+    pub fn tile_map(&self) -> &TileMap {
+        &self.tile_map
+    }
+
+    /// Replaces the tile map while keeping the covers and marks, used when
+    /// the bombs are generated after the game started
+    pub fn set_tile_map(&mut self, tile_map: TileMap) {
+        self.tile_map = tile_map;
+    }
+
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn is_covered(&self, coords: &Coordinates) -> bool {
+        self.covered.contains(coords)
+    }
+
+    /// Mark of a covered tile
+    pub fn mark(&self, coords: &Coordinates) -> Option<TileMark> {
+        self.marks
+            .get(coords)
+            .copied()
+            .filter(|_| self.is_covered(coords))
+    }
+
+    pub fn is_flagged(&self, coords: &Coordinates) -> bool {
+        matches!(
+            self.mark(coords),
+            Some(TileMark::Flag(_) | TileMark::AntiFlag(_))
+        )
+    }
+
+    pub fn covered_tiles(&self) -> impl Iterator<Item = &Coordinates> {
+        self.covered.iter()
+    }
+
+    /// Marks of the covered tiles
+    pub fn marks(&self) -> impl Iterator<Item = (&Coordinates, TileMark)> {
+        self.marks
+            .iter()
+            .filter(|(c, _)| self.is_covered(c))
+            .map(|(c, mark)| (c, *mark))
+    }
+
+    pub fn flagged_tiles(&self) -> impl Iterator<Item = &Coordinates> {
+        self.marks()
+            .filter(|(_, mark)| matches!(mark, TileMark::Flag(_) | TileMark::AntiFlag(_)))
+            .map(|(c, _)| c)
+    }
+
+    /// Coordinates of every uncovered tile
+    pub fn revealed_tiles(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        let width = self.tile_map.width();
+        (0..self.tile_map.height())
+            .flat_map(move |y| (0..width).map(move |x| Coordinates { x, y }))
+            .filter(|c| !self.is_covered(c) && !self.tile_map.is_hole(*c))
+    }
+
+    /// Uncovers `coords`, flooding through empty tiles.
+    /// Returns every uncovered tile, flagged tiles are left covered
+    pub fn reveal(&mut self, coords: Coordinates) -> Vec<Coordinates> {
+        if self.status != GameStatus::Playing
+            || !self.is_covered(&coords)
+            || self.is_flagged(&coords)
+        {
+            return vec![];
+        }
+
+        if self.tile_map.is_bomb_at(coords) {
+            self.covered.remove(&coords);
+            self.status = GameStatus::Lost(coords);
+            return vec![coords];
+        }
+
+        let mut revealed = vec![];
+        let mut stack = vec![coords];
+        while let Some(coords) = stack.pop() {
+            if self.is_flagged(&coords) || !self.covered.remove(&coords) {
+                continue;
+            }
+            revealed.push(coords);
+            if self.tile_map[coords.y as usize][coords.x as usize] == Tile::Empty {
+                stack.extend(self.tile_map.surrounding_tiles(coords));
+            }
+        }
+
+        if self.only_bombs_covered() {
+            self.status = GameStatus::Won;
+        }
+        revealed
+    }
+
+    /// Cycles the mark of a covered tile through one flag per mine the tile
+    /// can hide, the same for negative mines if the map has any, question
+    /// mark when `question_marks` is enabled, and no mark.
+    /// Returns the new mark
+    pub fn cycle_mark(
+        &mut self,
+        coords: Coordinates,
+        question_marks: bool,
+    ) -> Option<Option<TileMark>> {
+        if self.status != GameStatus::Playing || !self.is_covered(&coords) {
+            return None;
+        }
+        let max_mines = self.tile_map.max_mines();
+        let anti_flags = self.tile_map.anti_bomb_count() > 0;
+        let mark = match self.mark(&coords) {
+            None => Some(TileMark::Flag(1)),
+            Some(TileMark::Flag(count)) if count < max_mines => Some(TileMark::Flag(count + 1)),
+            Some(TileMark::Flag(_)) if anti_flags => Some(TileMark::AntiFlag(1)),
+            Some(TileMark::AntiFlag(count)) if count < max_mines => {
+                Some(TileMark::AntiFlag(count + 1))
+            }
+            Some(TileMark::Flag(_) | TileMark::AntiFlag(_)) if question_marks => {
+                Some(TileMark::Question)
+            }
+            Some(_) => None,
+        };
+        self.set_mark(coords, mark);
+        Some(mark)
+    }
+
+    /// Uncovers every unflagged bomb once the game is lost, returning their coordinates
+    pub fn reveal_bombs(&mut self) -> Vec<Coordinates> {
+        if !matches!(self.status, GameStatus::Lost(_)) {
+            return vec![];
+        }
+        let bombs: Vec<_> = self
+            .covered
+            .iter()
+            .filter(|c| self.tile_map.is_bomb_at(**c) && !self.is_flagged(c))
+            .copied()
+            .collect();
+        for coords in bombs.iter() {
+            self.covered.remove(coords);
+        }
+        bombs
+    }
+
+    /// Flags every remaining bomb with its mine count once the game is won,
+    /// returning their coordinates with their previous mark
+    pub fn flag_bombs(&mut self) -> Vec<(Coordinates, Option<TileMark>)> {
+        if self.status != GameStatus::Won {
+            return vec![];
+        }
+        let bombs: Vec<_> = self
+            .covered
+            .iter()
+            .filter(|c| self.mark(c) != Some(self.full_flag(c)))
+            .map(|c| (*c, self.mark(c)))
+            .collect();
+        for (coords, _) in bombs.iter() {
+            self.marks.insert(*coords, self.full_flag(coords));
+        }
+        bombs
+    }
+
+    /// Flag of the right sign marking every mine of the tile at `coords`
+    pub(crate) fn full_flag(&self, coords: &Coordinates) -> TileMark {
+        let count = self.tile_map.mines_at(*coords);
+        match self.tile_map[coords.y as usize][coords.x as usize] {
+            Tile::AntiBomb(_) => TileMark::AntiFlag(count),
+            _ => TileMark::Flag(count),
+        }
+    }
+
+    /// Flagged tiles whose flag count or sign doesn't match their mines
+    pub fn wrong_flags(&self) -> impl Iterator<Item = &Coordinates> {
+        self.flagged_tiles()
+            .filter(|c| self.mark(c) != Some(self.full_flag(c)))
+    }
+
+    /// Total number of flags of either sign on covered tiles
+    pub fn flag_count(&self) -> u32 {
+        self.marks().map(|(_, mark)| mark.flag_count() as u32).sum()
+    }
+
+    /// Covers `coords` back, used to undo reveals
+    pub fn cover(&mut self, coords: &[Coordinates]) {
+        self.covered.extend(coords.iter().copied());
+        self.update_status();
+    }
+
+    /// Uncovers exactly `coords` without flooding, used to redo reveals
+    pub fn uncover(&mut self, coords: &[Coordinates]) {
+        for c in coords {
+            self.covered.remove(c);
+        }
+        self.update_status();
+    }
+
+    /// Sets or clears the mark of a tile regardless of the game status, used to undo marks
+    pub fn set_mark(&mut self, coords: Coordinates, mark: Option<TileMark>) {
+        match mark {
+            Some(mark) => self.marks.insert(coords, mark),
+            None => self.marks.remove(&coords),
+        };
+    }
+
+    /// Return true once every safe tile is uncovered
+    fn only_bombs_covered(&self) -> bool {
+        self.covered.iter().all(|c| self.tile_map.is_bomb_at(*c))
+    }
+
+    /// Recomputes the status from the uncovered tiles
+    fn update_status(&mut self) {
+        let uncovered_bomb = self.revealed_tiles().find(|c| self.tile_map.is_bomb_at(*c));
+        self.status = match uncovered_bomb {
+            Some(coords) => GameStatus::Lost(coords),
+            None if self.only_bombs_covered() => GameStatus::Won,
+            None => GameStatus::Playing,
+        };
+    }
+
+    /// Uncovers the unflagged neighbors of an uncovered counter once its
+    /// adjacent flags add up to its counter, returning every uncovered tile
+    pub fn chord(&mut self, coords: Coordinates) -> Vec<Coordinates> {
+        if self.status != GameStatus::Playing || self.is_covered(&coords) {
+            return vec![];
+        }
+        let Tile::Neighbor(count) = self.tile_map[coords.y as usize][coords.x as usize] else {
+            return vec![];
+        };
+        let flags: i32 = self
+            .tile_map
+            .surrounding_tiles(coords)
+            .filter_map(|c| self.mark(&c))
+            .map(|mark| mark.value())
+            .sum();
+        if flags != count as i32 {
+            return vec![];
+        }
+
+        let targets: Vec<_> = self.tile_map.surrounding_tiles(coords).collect();
+        targets.into_iter().flat_map(|c| self.reveal(c)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(x: u16, y: u16) -> Coordinates {
+        Coordinates { x, y }
+    }
+
+    /// 3x3 game with a single mine at `mine`
+    fn game(mine: Coordinates) -> GameCore {
+        let mut tile_map = TileMap::empty(3, 3);
+        tile_map.add_bombs_at(&[mine]);
+        GameCore::new(tile_map)
+    }
+
+    #[test]
+    fn reveal_floods_empty_tiles() {
+        let mut core = game(coords(2, 2));
+        let revealed = core.reveal(coords(0, 0));
+
+        assert_eq!(revealed.len(), 8);
+        assert!(core.is_covered(&coords(2, 2)));
+        assert_eq!(core.status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn reveal_stops_at_counters_and_flags() {
+        let mut core = game(coords(0, 0));
+        core.cycle_mark(coords(2, 2), false);
+
+        assert_eq!(core.reveal(coords(1, 1)), vec![coords(1, 1)]);
+        assert!(core.reveal(coords(2, 2)).is_empty());
+        assert_eq!(core.status(), GameStatus::Playing);
+    }
+
+    #[test]
+    fn revealing_a_bomb_loses() {
+        let mut core = game(coords(2, 2));
+
+        assert_eq!(core.reveal(coords(2, 2)), vec![coords(2, 2)]);
+        assert_eq!(core.status(), GameStatus::Lost(coords(2, 2)));
+        assert!(core.reveal(coords(0, 0)).is_empty());
+    }
+
+    #[test]
+    fn chord_needs_matching_flags() {
+        let mut core = game(coords(0, 0));
+        core.reveal(coords(1, 1));
+        assert!(core.chord(coords(1, 1)).is_empty());
+
+        core.cycle_mark(coords(0, 0), false);
+        assert_eq!(core.chord(coords(1, 1)).len(), 7);
+        assert_eq!(core.status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn chord_on_wrong_flag_loses() {
+        let mut core = game(coords(0, 0));
+        core.reveal(coords(1, 1));
+        core.cycle_mark(coords(1, 0), false);
+        core.chord(coords(1, 1));
+
+        assert_eq!(core.status(), GameStatus::Lost(coords(0, 0)));
+    }
+
+    #[test]
+    fn mark_cycling() {
+        let mut core = game(coords(0, 0));
+        let tile = coords(1, 1);
+        assert_eq!(core.cycle_mark(tile, true), Some(Some(TileMark::Flag(1))));
+        assert_eq!(core.cycle_mark(tile, true), Some(Some(TileMark::Question)));
+        assert_eq!(core.cycle_mark(tile, true), Some(None));
+        assert_eq!(core.cycle_mark(tile, false), Some(Some(TileMark::Flag(1))));
+        assert_eq!(core.cycle_mark(tile, false), Some(None));
+
+        core.reveal(tile);
+        assert_eq!(core.cycle_mark(tile, false), None);
+    }
+
+    #[test]
+    fn mark_cycling_with_stacked_and_negative_mines() {
+        let mut tile_map = TileMap::empty(3, 3).with_max_mines(2).with_anti_bombs(1);
+        tile_map.add_bombs(1, 0).unwrap();
+        let mut core = GameCore::new(tile_map);
+        let tile = coords(1, 1);
+
+        let marks: Vec<_> = (0..5).map(|_| core.cycle_mark(tile, false)).collect();
+        assert_eq!(
+            marks,
+            [
+                Some(Some(TileMark::Flag(1))),
+                Some(Some(TileMark::Flag(2))),
+                Some(Some(TileMark::AntiFlag(1))),
+                Some(Some(TileMark::AntiFlag(2))),
+                Some(None),
+            ]
+        );
+    }
+}
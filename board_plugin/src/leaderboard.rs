@@ -0,0 +1,136 @@
+use crate::components::LeaderboardScreen;
+use crate::events::BoardCompletedEvent;
+use crate::resources::{
+    Board, BoardAssets, BoardOptions, Leaderboard, LeaderboardEntry, LeaderboardKey, ReplayPlayer,
+};
+
+use bevy::log;
+use bevy::prelude::*;
+use chrono::Utc;
+
+/// Ranks completed boards in the `Leaderboard` resource inserted by the host
+/// and lists the best games of the current `BoardOptions` while in `screen_state`
+///
+/// Restored saves and replays aren't ranked, and each board only once
+pub struct LeaderboardPlugin<T> {
+    pub screen_state: T,
+}
+
+impl<T: States> Plugin for LeaderboardPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            Self::record_game
+                .run_if(resource_exists::<Leaderboard>())
+                .run_if(resource_exists::<Board>()),
+        )
+        .add_systems(
+            OnEnter(self.screen_state.clone()),
+            Self::spawn_screen
+                .run_if(resource_exists::<Leaderboard>())
+                .run_if(resource_exists::<BoardOptions>()),
+        )
+        .add_systems(OnExit(self.screen_state.clone()), Self::despawn_screen);
+    }
+}
+
+impl<T: States> LeaderboardPlugin<T> {
+    fn record_game(
+        mut board: ResMut<Board>,
+        player: Option<Res<ReplayPlayer>>,
+        mut leaderboard: ResMut<Leaderboard>,
+        mut board_completed_rdr: EventReader<BoardCompletedEvent>,
+    ) {
+        for event in board_completed_rdr.read() {
+            if board.restored || player.is_some() || board.ranked {
+                log::info!("Game not ranked, restored from a save, replayed or already ranked");
+                continue;
+            }
+            board.ranked = true;
+            let entry = LeaderboardEntry {
+                time: event.elapsed,
+                three_bv_per_second: event.three_bv_per_second(),
+                seed: event.seed,
+                date: Utc::now(),
+            };
+            let Some(rank) = leaderboard.record(LeaderboardKey::new(&board.options), entry) else {
+                continue;
+            };
+            log::info!("New best time, ranked #{}", rank + 1);
+            if let Err(e) = leaderboard.save() {
+                log::error!(
+                    "Failed to save leaderboard to {}: {e}",
+                    leaderboard.path.display()
+                );
+            }
+        }
+    }
+
+    /// Spawns the table of the best games of the current board, or of the
+    /// board options without one
+    fn spawn_screen(
+        mut commands: Commands,
+        leaderboard: Res<Leaderboard>,
+        options: Res<BoardOptions>,
+        board: Option<Res<Board>>,
+        board_assets: Res<BoardAssets>,
+    ) {
+        // the board options resolve mask and layout files
+        let options = board.as_ref().map_or(&*options, |board| &board.options);
+        let key = LeaderboardKey::new(options);
+        let style = TextStyle {
+            font: board_assets.bomb_counter_font.clone(),
+            font_size: 16.,
+            color: Color::WHITE,
+        };
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(Name::new("Leaderboard"))
+            .insert(LeaderboardScreen)
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    "Best times",
+                    TextStyle {
+                        font_size: 30.,
+                        ..style.clone()
+                    },
+                ));
+                parent.spawn(TextBundle::from_section(key.to_string(), style.clone()));
+                let entries = leaderboard.entries(&key);
+                if entries.is_empty() {
+                    parent.spawn(TextBundle::from_section("No games yet", style.clone()));
+                }
+                for (rank, entry) in entries.iter().enumerate() {
+                    parent.spawn(TextBundle::from_section(
+                        format!(
+                            "{:>2}. {:>7.2}s {:>5.2} 3BV/s {} seed {}",
+                            rank + 1,
+                            entry.time.as_secs_f32(),
+                            entry.three_bv_per_second,
+                            entry.date.date_naive(),
+                            entry.seed
+                        ),
+                        style.clone(),
+                    ));
+                }
+            });
+    }
+
+    fn despawn_screen(mut commands: Commands, screens: Query<Entity, With<LeaderboardScreen>>) {
+        for entity in screens.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -1,27 +1,54 @@
 mod bounds;
 pub mod components;
-mod events;
+pub mod events;
+pub mod files;
+pub mod game_core;
+pub mod leaderboard;
+pub mod menu;
+pub mod metrics;
+pub mod replay;
 pub mod resources;
+pub mod solver;
+pub mod stats;
 mod systems;
 
 pub use bounds::Bounds2;
+pub use files::BoardFilesPlugin;
+pub use game_core::{GameCore, GameStatus, TileMark};
+pub use leaderboard::LeaderboardPlugin;
+pub use menu::MenuPlugin;
+pub use replay::ReplayPlugin;
+pub use stats::StatsPlugin;
 
+use bevy::ecs::system::EntityCommands;
 use bevy::log;
 use bevy::prelude::*;
 use bevy::text::{Text, TextAlignment};
-use bevy::utils::HashMap;
+use bevy::utils::{Duration, HashMap, HashSet};
 use bevy::window::PrimaryWindow;
 use components::*;
 use events::*;
+use metrics::BoardMetrics;
 use resources::{
-    board::Board, tile::Tile, tile_map::TileMap, BoardAssets, BoardOptions, BoardPosition, TileSize,
+    board::Board, tile::Tile, tile_map::TileMap, BoardAssets, BoardLayout, BoardOptions,
+    BoardPosition, GameClock, Generation, History, MaskSource, MineCounter, PlacementError,
+    ReplayPlayer, SafeStart, SavedGame, ShapeMask, SpriteMaterial, TileSize, Topology,
+};
+use std::path::Path;
+use systems::{
+    board_completed_handler, bomb_explosion_handler, chord_event_handler, completed_event,
+    input_handling, mark_tiles, save_game, spawn_flag, spawn_mark, tick_game_clock,
+    trigger_event_handler, uncover_tiles, update_hud, update_mine_counter,
 };
-use systems::{input_handling, mark_tiles, trigger_event_handler, uncover_tiles};
 
 pub struct BoardPlugin<T> {
     pub start_state: T,
     pub running_state: T,
     pub end_state: T,
+    /// State the host moves to when a bomb explodes, the board stays displayed
+    pub lost_state: Option<T>,
+    /// State the host moves to when the board is completed, the board stays displayed
+    pub won_state: Option<T>,
 }
 
 impl<T: States> Plugin for BoardPlugin<T> {
@@ -29,20 +56,126 @@ impl<T: States> Plugin for BoardPlugin<T> {
         log::info!("Loading BoardPlugin");
         app.add_event::<TileTriggerEvent>()
             .add_event::<TileMarkEvent>()
+            .add_event::<TileChordEvent>()
             .add_event::<BombExplosionEvent>()
             .add_event::<BoardCompletedEvent>()
+            .add_event::<BoardErrorEvent>()
+            .add_event::<SaveGameEvent>()
+            .add_event::<LoadGameEvent>()
+            .add_event::<UndoEvent>()
+            .add_event::<RedoEvent>()
+            // boards replaced while running are despawned once the running
+            // systems stopped
+            .add_systems(
+                OnEnter(self.start_state.clone()),
+                Self::cleanup.run_if(resource_exists::<Board>()),
+            )
             .add_systems(OnExit(self.start_state.clone()), Self::create_board)
-            .add_systems(OnEnter(self.end_state.clone()), Self::cleanup)
+            .add_systems(
+                OnEnter(self.end_state.clone()),
+                Self::cleanup.run_if(resource_exists::<Board>()),
+            )
             .add_systems(
                 Update,
                 (
-                    input_handling,
+                    input_handling.run_if(not(resource_exists::<ReplayPlayer>())),
+                    Self::generate_on_first_click
+                        .after(input_handling)
+                        .before(trigger_event_handler)
+                        .before(chord_event_handler),
                     trigger_event_handler,
-                    uncover_tiles,
+                    chord_event_handler,
                     mark_tiles,
+                    (bomb_explosion_handler, board_completed_handler)
+                        .after(trigger_event_handler)
+                        .after(chord_event_handler),
+                    tick_game_clock,
                 )
+                    .run_if(resource_exists::<Board>())
+                    .run_if(in_state(self.running_state.clone())),
+            )
+            // covers keep getting removed once the host leaves the running state
+            .add_systems(Update, uncover_tiles)
+            .add_systems(
+                Update,
+                (
+                    Self::undo_action,
+                    Self::redo_action,
+                    update_mine_counter,
+                    update_hud,
+                    save_game,
+                )
+                    .chain()
+                    .run_if(resource_exists::<Board>()),
+            )
+            .add_systems(
+                Update,
+                Self::update_ghost_tiles
+                    .after(save_game)
+                    .run_if(resource_exists::<Board>().and_then(resource_changed::<Board>())),
+            )
+            .add_systems(
+                Update,
+                (
+                    Self::load_game,
+                    Self::enter(self.start_state.clone()).run_if(resource_added::<SavedGame>()),
+                )
+                    .chain(),
+            );
+
+        for state in [&self.lost_state, &self.won_state].into_iter().flatten() {
+            app.add_systems(
+                Update,
+                Self::resume_on_undo(self.running_state.clone())
+                    .after(Self::undo_action)
+                    .run_if(in_state(state.clone())),
+            );
+        }
+        if let Some(state) = &self.lost_state {
+            app.add_systems(
+                Update,
+                Self::transition_on::<BombExplosionEvent>(state.clone())
+                    .after(trigger_event_handler)
+                    .after(chord_event_handler)
                     .run_if(in_state(self.running_state.clone())),
             );
+        }
+        if let Some(state) = &self.won_state {
+            app.add_systems(
+                Update,
+                Self::transition_on::<BoardCompletedEvent>(state.clone())
+                    .after(trigger_event_handler)
+                    .after(chord_event_handler)
+                    .run_if(in_state(self.running_state.clone())),
+            );
+        }
+    }
+}
+
+impl<T: States> BoardPlugin<T> {
+    /// System moving the host app back to `state` once an undo resumes a finished game
+    fn resume_on_undo(
+        state: T,
+    ) -> impl FnMut(EventReader<UndoEvent>, Res<Board>, ResMut<NextState<T>>) {
+        move |mut events, board, mut next| {
+            if events.read().last().is_some() && board.core.status() == GameStatus::Playing {
+                next.set(state.clone());
+            }
+        }
+    }
+
+    /// System moving the host app to `state`
+    fn enter(state: T) -> impl FnMut(ResMut<NextState<T>>) {
+        move |mut next| next.set(state.clone())
+    }
+
+    /// System moving the host app to `state` when an `E` event is received
+    fn transition_on<E: Event>(state: T) -> impl FnMut(EventReader<E>, ResMut<NextState<T>>) {
+        move |mut events, mut next| {
+            if events.read().last().is_some() {
+                next.set(state.clone());
+            }
+        }
     }
 }
 
@@ -51,29 +184,94 @@ impl<T> BoardPlugin<T> {
         mut commands: Commands,
         board_options: Option<Res<BoardOptions>>,
         board_assets: Res<BoardAssets>,
+        saved_game: Option<Res<SavedGame>>,
+        replay: Option<Res<ReplayPlayer>>,
         query: Query<&Window, With<PrimaryWindow>>,
+        mut board_error_ewr: EventWriter<BoardErrorEvent>,
     ) {
-        let options = match board_options {
-            None => BoardOptions::default(),
-            Some(o) => o.clone(),
+        let saved_game = saved_game.map(|saved| saved.clone());
+        commands.remove_resource::<SavedGame>();
+
+        let mut options = match (&saved_game, replay, board_options) {
+            (Some(saved), _, _) => saved.options.clone(),
+            (None, Some(replay), _) => replay.replay.options.clone(),
+            (None, None, None) => BoardOptions::default(),
+            (None, None, Some(o)) => o.clone(),
         };
 
-        let mut tile_map = TileMap::empty(options.map_size.0, options.map_size.1);
+        if let Some(MaskSource::File(path)) = &options.mask {
+            match ShapeMask::load(path) {
+                Ok(mask) => {
+                    log::info!("Loading board mask from {}", path.display());
+                    options.mask = Some(MaskSource::Inline(mask));
+                }
+                Err(e) => {
+                    log::error!("Failed to load board mask from {}: {e}", path.display());
+                    board_error_ewr.send(BoardErrorEvent(e.into()));
+                    return;
+                }
+            }
+        }
+
+        let layout = match (&saved_game, options.generation.clone()) {
+            (None, Generation::FromFile(path)) => Self::load_layout(&mut options, &path),
+            _ => None,
+        };
+
+        // saved games were validated by `SavedGame::read`
+        if saved_game.is_none() {
+            if let Err(e) = options.validate() {
+                log::error!("Invalid board options: {e}");
+                board_error_ewr.send(BoardErrorEvent(e.into()));
+                return;
+            }
+        }
+
+        let (tile_map, seed, generated) = match (&saved_game, &layout) {
+            (Some(saved), _) => {
+                log::info!("Restoring saved board with seed {}", saved.seed);
+                (saved.tile_map.clone(), saved.seed, saved.generated)
+            }
+            (None, Some((tile_map, _))) => {
+                (tile_map.clone(), options.seed.unwrap_or_default(), true)
+            }
+            (None, None) => {
+                let seed = options.seed.unwrap_or_else(rand::random);
+                let generated = !matches!(options.safe_start, SafeStart::FirstClick { .. });
+                let tile_map = if generated {
+                    log::info!("Generating board with seed {seed}");
+                    match Self::generate_tile_map(&options, seed, None) {
+                        Ok(tile_map) => tile_map,
+                        Err(e) => {
+                            log::error!("Failed to place the mines: {e}");
+                            board_error_ewr.send(BoardErrorEvent(e.into()));
+                            return;
+                        }
+                    }
+                } else {
+                    Self::empty_tile_map(&options)
+                };
+                (tile_map, seed, generated)
+            }
+        };
         let window = query.single();
 
         let tile_size = match options.tile_size {
             TileSize::Fixed(s) => s,
             TileSize::Adaptive { min, max } => {
-                Self::adaptive_tile_size(window, (min, max), (tile_map.width(), tile_map.height()))
+                let mut unit_size = options
+                    .topology
+                    .board_size((tile_map.width(), tile_map.height()), 1.);
+                if let Topology::Toroidal { ghost_edges: true } = options.topology {
+                    unit_size += 2.;
+                }
+                Self::adaptive_tile_size(window, (min, max), unit_size)
             }
         };
 
-        tile_map.add_bombs(options.bomb_count);
-
-        let board_size = Vec2::new(
-            tile_map.width() as f32 * tile_size,
-            tile_map.height() as f32 * tile_size,
-        );
+        let board_size = options
+            .topology
+            .board_size((tile_map.width(), tile_map.height()), tile_size);
 
         let position = match options.position {
             BoardPosition::Centered { offset } => {
@@ -85,8 +283,6 @@ impl<T> BoardPlugin<T> {
         let mut covered_tiles =
             HashMap::with_capacity((tile_map.width() * tile_map.height()).into());
 
-        let mut safe_start = None;
-
         let board_entity = commands
             .spawn(SpatialBundle {
                 visibility: Visibility::Visible,
@@ -106,7 +302,8 @@ impl<T> BoardPlugin<T> {
                         transform: Transform::from_xyz(board_size.x / 2., board_size.y / 2., 0.),
                         ..default()
                     })
-                    .insert(Name::new("Background"));
+                    .insert(Name::new("Background"))
+                    .insert(BoardBackground);
 
                 Self::spawn_tiles(
                     parent,
@@ -115,34 +312,187 @@ impl<T> BoardPlugin<T> {
                     options.tile_padding,
                     &board_assets,
                     &mut covered_tiles,
-                    &mut safe_start,
                 );
+
+                if let Topology::Toroidal { ghost_edges: true } = options.topology {
+                    Self::spawn_ghost_tiles(
+                        parent,
+                        &tile_map,
+                        tile_size,
+                        options.tile_padding,
+                        &board_assets,
+                    );
+                }
             })
             .id();
 
-        if options.safe_start {
-            if let Some(entity) = safe_start {
-                commands.entity(entity).insert(Uncover);
-            }
-        }
-
         #[cfg(feature = "debug")]
         log::info!("{}", tile_map.console_output());
 
-        commands.insert_resource(Board {
-            tile_map,
+        let safe_start = tile_map.first_empty_tile();
+        let history = options.history.then(History::default);
+        let metrics = generated.then(|| BoardMetrics::new(&tile_map, &options.generation));
+        // uncovered and marked tiles of a saved game or layout file
+        let state = match (&saved_game, &layout) {
+            (Some(saved), _) => Some((saved.revealed.clone(), saved.marks().collect::<Vec<_>>())),
+            (None, Some((_, layout))) => Some((layout.revealed.clone(), layout.marks().collect())),
+            (None, None) => None,
+        };
+        let core = match &state {
+            Some((revealed, marks)) => GameCore::restore(tile_map, revealed, marks.iter().copied()),
+            None => GameCore::new(tile_map),
+        };
+        let mut board = Board {
+            core,
             tile_size,
             bounds: Bounds2 {
                 position: position.truncate(),
                 size: board_size,
             },
             covered_tiles,
-            marked_tiles: vec![],
             entity: board_entity,
-        });
+            seed,
+            options,
+            generated,
+            clicks: Default::default(),
+            restored: saved_game.is_some(),
+            recorded: false,
+            ranked: false,
+            history,
+            metrics,
+        };
+
+        if let Some((revealed, marks)) = &state {
+            for coords in revealed.iter() {
+                if let Some(entity) = board.covered_tiles.remove(coords) {
+                    commands.entity(entity).insert(Uncover);
+                }
+            }
+            for (coords, mark) in marks.iter() {
+                if let Some(entity) = board.covered_tiles.get(coords) {
+                    spawn_mark(&mut commands, *entity, &board_assets, *mark, tile_size);
+                }
+            }
+        }
+
+        let clock = match &saved_game {
+            Some(saved) => {
+                board.clicks = saved.clicks;
+                let mut clock = GameClock::resumed(saved.elapsed);
+                if board.core.status() != GameStatus::Playing {
+                    clock.stop();
+                }
+                clock
+            }
+            None => {
+                // layout files may already uncover some tiles
+                let untouched = board.core.revealed_tiles().next().is_none();
+                if let (SafeStart::FirstEmpty, Some(coords), true) =
+                    (board.options.safe_start, safe_start, untouched)
+                {
+                    for entity in board.reveal(&coords) {
+                        commands.entity(entity).insert(Uncover);
+                    }
+                }
+                GameClock::default()
+            }
+        };
+
+        let mines = board.remaining_mines();
+        if board.options.hud {
+            Self::spawn_hud(&mut commands, &board_assets, mines);
+        }
+        commands.insert_resource(clock);
+        commands.insert_resource(MineCounter(mines));
+        commands.insert_resource(board);
+    }
+
+    /// Spawns the mine counter and clock UI above the board
+    fn spawn_hud(commands: &mut Commands, board_assets: &BoardAssets, mines: i32) {
+        let style = TextStyle {
+            font: board_assets.bomb_counter_font.clone(),
+            font_size: 30.,
+            color: Color::WHITE,
+        };
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.),
+                    width: Val::Percent(100.),
+                    justify_content: JustifyContent::SpaceEvenly,
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(Name::new("HUD"))
+            .insert(Hud)
+            .with_children(|parent| {
+                parent
+                    .spawn(TextBundle::from_section(
+                        format!("{mines:03}"),
+                        style.clone(),
+                    ))
+                    .insert(MineCounterText);
+                parent
+                    .spawn(TextBundle::from_section("000", style))
+                    .insert(ClockText);
+            });
+    }
+
+    /// Places the bombs around the first triggered tile when generation is
+    /// deferred by `SafeStart::FirstClick`. On failure the board stays without
+    /// bombs and ignores reveals, the host is told by a `BoardErrorEvent`
+    fn generate_on_first_click(
+        mut commands: Commands,
+        mut board: ResMut<Board>,
+        board_assets: Res<BoardAssets>,
+        mut tile_trigger: EventReader<TileTriggerEvent>,
+        mut board_error_ewr: EventWriter<BoardErrorEvent>,
+        tiles: Query<(Entity, &Coordinates)>,
+    ) {
+        if board.generated {
+            return;
+        }
+        let Some(TileTriggerEvent(start)) = tile_trigger.read().next().copied() else {
+            return;
+        };
+        let SafeStart::FirstClick { radius } = board.options.safe_start else {
+            return;
+        };
+
+        log::info!("Generating board with seed {} around {start}", board.seed);
+        let tile_map =
+            match Self::generate_tile_map(&board.options, board.seed, Some((start, radius))) {
+                Ok(tile_map) => tile_map,
+                Err(e) => {
+                    log::error!("Failed to place the mines: {e}");
+                    board_error_ewr.send(BoardErrorEvent(e.into()));
+                    return;
+                }
+            };
+        board.core.set_tile_map(tile_map);
+        board.generated = true;
+        board.metrics = Some(BoardMetrics::new(
+            board.tile_map(),
+            &board.options.generation,
+        ));
+
+        #[cfg(feature = "debug")]
+        log::info!("{}", board.tile_map().console_output());
+
+        for (entity, coords) in tiles.iter() {
+            let tile = &board.tile_map()[coords.y as usize][coords.x as usize];
+            Self::spawn_tile_content(
+                &mut commands.entity(entity),
+                tile,
+                board.tile_size,
+                board.options.tile_padding,
+                &board_assets,
+            );
+        }
     }
 
-    #[allow(clippy::too_many_arguments)]
     fn spawn_tiles(
         parent: &mut ChildBuilder,
         tile_map: &TileMap,
@@ -150,105 +500,557 @@ impl<T> BoardPlugin<T> {
         tile_padding: f32,
         board_assets: &BoardAssets,
         covered_tiles: &mut HashMap<Coordinates, Entity>,
-        safe_start_entity: &mut Option<Entity>,
     ) {
+        let topology = tile_map.topology();
         for (y, line) in tile_map.iter().enumerate() {
             for (x, tile) in line.iter().enumerate() {
+                if *tile == Tile::Hole {
+                    continue;
+                }
                 let mut cmd = parent.spawn_empty();
                 let coordinates = Coordinates {
                     x: x as u16,
                     y: y as u16,
                 };
-                cmd.insert(SpriteBundle {
-                    sprite: Sprite {
-                        color: board_assets.tile_material.color,
-                        custom_size: Some(Vec2::splat(tile_size - tile_padding)),
-                        ..default()
-                    },
-                    texture: board_assets.tile_material.texture.clone(),
-                    transform: Transform::from_xyz(
-                        (x as f32 * tile_size) + (tile_size / 2.),
-                        (y as f32 * tile_size) + (tile_size / 2.),
-                        1.,
-                    ),
-                    ..default()
-                })
+                let center = topology.tile_center(coordinates, tile_size);
+                cmd.insert(Self::tile_sprite_bundle(
+                    &board_assets.tile_material,
+                    topology,
+                    tile_size - tile_padding,
+                    board_assets,
+                    Transform::from_translation(center.extend(1.)),
+                ))
                 .insert(Name::new(format!("Tile ({x}, {y})")))
                 .insert(coordinates);
 
                 cmd.with_children(|parent| {
-                    let entity = parent
-                        .spawn(SpriteBundle {
-                            sprite: Sprite {
-                                color: board_assets.covered_tile_material.color,
-                                custom_size: Some(Vec2::splat(tile_size - tile_padding)),
-                                ..default()
-                            },
-                            transform: Transform::from_xyz(0., 0., 2.),
-                            texture: board_assets.covered_tile_material.texture.clone(),
-                            ..default()
-                        })
-                        .insert(Name::new("Tile Cover"))
-                        .id();
+                    let entity =
+                        Self::spawn_cover(parent, topology, tile_size, tile_padding, board_assets);
                     covered_tiles.insert(coordinates, entity);
-                    if safe_start_entity.is_none() && *tile == Tile::Empty {
-                        *safe_start_entity = Some(entity);
-                    }
                 });
 
-                match tile {
-                    Tile::Bomb => {
-                        cmd.insert(Bomb);
-                        cmd.with_children(|parent| {
-                            parent.spawn(SpriteBundle {
-                                sprite: Sprite {
-                                    custom_size: Some(Vec2::splat(tile_size - tile_padding)),
-                                    ..default()
-                                },
-                                transform: Transform::from_xyz(0., 0., 1.),
-                                texture: board_assets.bomb_material.texture.clone(),
-                                ..default()
-                            });
-                        });
-                    }
-                    Tile::Neighbor(count) => {
-                        cmd.insert(Neighbor { count: *count });
-                        cmd.with_children(|parent| {
-                            parent.spawn(Self::bomb_count_text_bundle(
-                                *count,
-                                board_assets,
-                                tile_size - tile_padding,
-                            ));
-                        });
+                Self::spawn_tile_content(&mut cmd, tile, tile_size, tile_padding, board_assets);
+            }
+        }
+    }
+
+    /// Applies new `BoardAssets` to the current board, respawning the tile
+    /// contents, covers and marks from the board state
+    pub(crate) fn reskin_board(
+        mut commands: Commands,
+        mut board: ResMut<Board>,
+        board_assets: Res<BoardAssets>,
+        tiles: Query<(Entity, &Coordinates)>,
+        backgrounds: Query<Entity, With<BoardBackground>>,
+        mut ghosts: Query<(Entity, &mut GhostTile)>,
+    ) {
+        let (tile_size, tile_padding) = (board.tile_size, board.options.tile_padding);
+        let topology = board.tile_map().topology();
+        for (entity, coords) in tiles.iter() {
+            let tile = board.tile_map()[coords.y as usize][coords.x as usize];
+            let center = topology.tile_center(*coords, tile_size);
+            let mut cmd = commands.entity(entity);
+            cmd.despawn_descendants().insert(Self::tile_sprite_bundle(
+                &board_assets.tile_material,
+                topology,
+                tile_size - tile_padding,
+                &board_assets,
+                Transform::from_translation(center.extend(1.)),
+            ));
+            Self::spawn_tile_content(&mut cmd, &tile, tile_size, tile_padding, &board_assets);
+            if !board.covered_tiles.contains_key(coords) {
+                continue;
+            }
+
+            let mut cover = None;
+            cmd.with_children(|parent| {
+                cover = Some(Self::spawn_cover(
+                    parent,
+                    topology,
+                    tile_size,
+                    tile_padding,
+                    &board_assets,
+                ));
+            });
+            let Some(cover) = cover else {
+                continue;
+            };
+            board.covered_tiles.insert(*coords, cover);
+            if let Some(mark) = board.core.mark(coords) {
+                spawn_mark(&mut commands, cover, &board_assets, mark, tile_size);
+            }
+        }
+        if let GameStatus::Lost(_) = board.core.status() {
+            for entity in board.wrong_flags() {
+                commands.entity(entity).despawn_descendants();
+                spawn_flag(
+                    &mut commands,
+                    entity,
+                    &board_assets.wrong_flag_material,
+                    tile_size,
+                );
+            }
+        }
+
+        for entity in backgrounds.iter() {
+            let material = &board_assets.board_material;
+            commands.entity(entity).insert((
+                Sprite {
+                    color: material.color,
+                    custom_size: Some(board.bounds.size),
+                    ..default()
+                },
+                material.texture.clone(),
+            ));
+        }
+        // ghost tile contents are respawned by `update_ghost_tiles`
+        for (entity, mut ghost) in ghosts.iter_mut() {
+            ghost.shown = None;
+            let material = &board_assets.tile_material;
+            commands.entity(entity).insert((
+                Sprite {
+                    color: material.color.with_a(0.5),
+                    custom_size: Some(topology.tile_extent(tile_size - tile_padding)),
+                    ..default()
+                },
+                material.texture.clone(),
+            ));
+        }
+    }
+
+    /// Applies the font of new `BoardAssets` to the HUD
+    pub(crate) fn reskin_hud(
+        board_assets: Res<BoardAssets>,
+        huds: Query<&Children, With<Hud>>,
+        mut texts: Query<&mut Text>,
+    ) {
+        for children in huds.iter() {
+            let mut hud_texts = texts.iter_many_mut(children);
+            while let Some(mut text) = hud_texts.fetch_next() {
+                for section in text.sections.iter_mut() {
+                    section.style.font = board_assets.bomb_counter_font.clone();
+                }
+            }
+        }
+    }
+
+    /// Spawns a ring of ghost tiles around a wrapping board, their content
+    /// is kept in sync by `update_ghost_tiles`
+    fn spawn_ghost_tiles(
+        parent: &mut ChildBuilder,
+        tile_map: &TileMap,
+        tile_size: f32,
+        tile_padding: f32,
+        board_assets: &BoardAssets,
+    ) {
+        let (width, height) = (tile_map.width() as i32, tile_map.height() as i32);
+        let material = SpriteMaterial {
+            color: board_assets.tile_material.color.with_a(0.5),
+            texture: board_assets.tile_material.texture.clone(),
+        };
+        for y in -1..=height {
+            for x in -1..=width {
+                if (0..width).contains(&x) && (0..height).contains(&y) {
+                    continue;
+                }
+                let source = Coordinates {
+                    x: x.rem_euclid(width) as u16,
+                    y: y.rem_euclid(height) as u16,
+                };
+                if tile_map.is_hole(source) {
+                    continue;
+                }
+                let center = (Vec2::new(x as f32, y as f32) + 0.5) * tile_size;
+                parent
+                    .spawn(Self::tile_sprite_bundle(
+                        &material,
+                        tile_map.topology(),
+                        tile_size - tile_padding,
+                        board_assets,
+                        Transform::from_translation(center.extend(1.)),
+                    ))
+                    .insert(Name::new(format!("Ghost Tile ({x}, {y})")))
+                    .insert(GhostTile {
+                        source,
+                        shown: None,
+                    });
+            }
+        }
+    }
+
+    /// Mirrors the state of the source tiles on the ghost tiles, respawning
+    /// the content of the ghosts whose source tile changed
+    fn update_ghost_tiles(
+        mut commands: Commands,
+        board: Res<Board>,
+        board_assets: Res<BoardAssets>,
+        mut ghosts: Query<(Entity, &mut GhostTile)>,
+    ) {
+        let (tile_size, tile_padding) = (board.tile_size, board.options.tile_padding);
+        let topology = board.tile_map().topology();
+        for (entity, mut ghost) in ghosts.iter_mut() {
+            let coords = ghost.source;
+            let state = GhostState {
+                generated: board.generated,
+                covered: board.core.is_covered(&coords),
+                mark: board.core.mark(&coords),
+            };
+            if ghost.shown == Some(state) {
+                continue;
+            }
+            ghost.shown = Some(state);
+
+            let mut cmd = commands.entity(entity);
+            cmd.despawn_descendants();
+            if state.generated {
+                let tile = &board.tile_map()[coords.y as usize][coords.x as usize];
+                Self::spawn_tile_sprites(&mut cmd, tile, tile_size, tile_padding, &board_assets);
+            }
+            if !state.covered {
+                continue;
+            }
+
+            let mut cover = None;
+            cmd.with_children(|parent| {
+                cover = Some(Self::spawn_cover(
+                    parent,
+                    topology,
+                    tile_size,
+                    tile_padding,
+                    &board_assets,
+                ));
+            });
+            if let (Some(cover), Some(mark)) = (cover, state.mark) {
+                spawn_mark(&mut commands, cover, &board_assets, mark, tile_size);
+            }
+        }
+    }
+
+    fn spawn_cover(
+        parent: &mut ChildBuilder,
+        topology: Topology,
+        tile_size: f32,
+        tile_padding: f32,
+        board_assets: &BoardAssets,
+    ) -> Entity {
+        parent
+            .spawn(Self::tile_sprite_bundle(
+                &board_assets.covered_tile_material,
+                topology,
+                tile_size - tile_padding,
+                board_assets,
+                Transform::from_xyz(0., 0., 2.),
+            ))
+            .insert(Name::new("Tile Cover"))
+            .id()
+    }
+
+    /// Tile or cover sprite shaped after `topology`, hexagonal tiles use the
+    /// hexagon mask instead of the material texture when there is one
+    fn tile_sprite_bundle(
+        material: &SpriteMaterial,
+        topology: Topology,
+        size: f32,
+        board_assets: &BoardAssets,
+        transform: Transform,
+    ) -> SpriteBundle {
+        let texture = match topology {
+            Topology::Square | Topology::Toroidal { .. } => material.texture.clone(),
+            Topology::Hexagonal { orientation, .. } => board_assets
+                .hex_texture(orientation)
+                .unwrap_or(&material.texture)
+                .clone(),
+        };
+        SpriteBundle {
+            sprite: Sprite {
+                color: material.color,
+                custom_size: Some(topology.tile_extent(size)),
+                ..default()
+            },
+            texture,
+            transform,
+            ..default()
+        }
+    }
+
+    /// Reverts the last board action, spawning back the covers of uncovered tiles
+    fn undo_action(
+        mut commands: Commands,
+        mut board: ResMut<Board>,
+        mut clock: ResMut<GameClock>,
+        board_assets: Res<BoardAssets>,
+        mut undo_rdr: EventReader<UndoEvent>,
+        tiles: Query<(Entity, &Coordinates)>,
+    ) {
+        for _ in undo_rdr.read() {
+            let was_lost = matches!(board.core.status(), GameStatus::Lost(_));
+            let Some(action) = board.undo() else {
+                continue;
+            };
+
+            let uncovered: HashSet<_> = action.uncovered.iter().collect();
+            for (entity, coords) in tiles.iter().filter(|(_, c)| uncovered.contains(c)) {
+                let (tile_size, tile_padding) = (board.tile_size, board.options.tile_padding);
+                let topology = board.tile_map().topology();
+                let mut cover = None;
+                commands.entity(entity).with_children(|parent| {
+                    cover = Some(Self::spawn_cover(
+                        parent,
+                        topology,
+                        tile_size,
+                        tile_padding,
+                        &board_assets,
+                    ));
+                });
+                board
+                    .covered_tiles
+                    .extend(cover.map(|cover| (*coords, cover)));
+            }
+
+            // covers put back keep their marks, and wrong flag sprites of a
+            // lost game go back to regular flags
+            let mut marks: Vec<_> = action.marks.iter().map(|m| m.coords).collect();
+            marks.extend(action.uncovered.iter().copied());
+            if was_lost {
+                marks.extend(board.marked_tiles().copied());
+            }
+            Self::refresh_marks(&mut commands, &board, &board_assets, &marks);
+
+            if board.core.status() == GameStatus::Playing {
+                clock.resume();
+            }
+        }
+    }
+
+    /// Applies the last reverted board action again
+    fn redo_action(
+        mut commands: Commands,
+        mut board: ResMut<Board>,
+        mut clock: ResMut<GameClock>,
+        board_assets: Res<BoardAssets>,
+        mut redo_rdr: EventReader<RedoEvent>,
+        mut board_completed_event_wr: EventWriter<BoardCompletedEvent>,
+        mut bomb_explosion_event_wr: EventWriter<BombExplosionEvent>,
+    ) {
+        for _ in redo_rdr.read() {
+            let Some((action, covers)) = board.redo() else {
+                continue;
+            };
+            for entity in covers {
+                commands.entity(entity).insert(Uncover);
+            }
+            let marks: Vec<_> = action.marks.iter().map(|m| m.coords).collect();
+            Self::refresh_marks(&mut commands, &board, &board_assets, &marks);
+
+            match board.core.status() {
+                GameStatus::Won => {
+                    clock.stop();
+                    board_completed_event_wr.send(completed_event(&board, &clock));
+                }
+                GameStatus::Lost(coords) => {
+                    clock.stop();
+                    bomb_explosion_event_wr.send(BombExplosionEvent(coords));
+                }
+                GameStatus::Playing => (),
+            }
+        }
+    }
+
+    /// Respawns the mark sprites of the covered tiles at `coords`
+    fn refresh_marks(
+        commands: &mut Commands,
+        board: &Board,
+        board_assets: &BoardAssets,
+        coords: &[Coordinates],
+    ) {
+        for c in coords {
+            let Some(entity) = board.covered_tiles.get(c) else {
+                continue;
+            };
+            commands.entity(*entity).despawn_descendants();
+            if let Some(mark) = board.core.mark(c) {
+                spawn_mark(commands, *entity, board_assets, mark, board.tile_size);
+            }
+        }
+    }
+
+    /// Empty tile map matching the size, shape, topology, neighborhood and
+    /// mine settings of `options`
+    fn empty_tile_map(options: &BoardOptions) -> TileMap {
+        let (width, height) = options
+            .shape_mask()
+            .map_or(options.map_size, ShapeMask::size);
+        let tile_map = TileMap::empty(width, height)
+            .with_topology(options.topology)
+            .with_neighborhood(options.neighborhood.clone())
+            .with_max_mines(options.max_mines_per_tile)
+            .with_anti_bombs(options.anti_bomb_count);
+        match options.shape_mask() {
+            Some(mask) => tile_map.with_mask(mask),
+            None => tile_map,
+        }
+    }
+
+    /// Reads the layout file of `Generation::FromFile`, resizing `options` to
+    /// the layout. Returns `None` if the layout is invalid
+    fn load_layout(options: &mut BoardOptions, path: &Path) -> Option<(TileMap, BoardLayout)> {
+        let loaded = BoardLayout::load(path).and_then(|layout| {
+            let template = Self::empty_tile_map(&BoardOptions {
+                map_size: layout.size(),
+                mask: None,
+                ..options.clone()
+            });
+            Ok((layout.tile_map(template)?, layout))
+        });
+        match loaded {
+            Ok((tile_map, layout)) => {
+                log::info!("Loading board layout from {}", path.display());
+                options.map_size = layout.size();
+                options.mask = None;
+                options.bomb_count = tile_map.bomb_count();
+                options.anti_bomb_count = tile_map.anti_bomb_count();
+                Some((tile_map, layout))
+            }
+            Err(e) => {
+                log::error!("Failed to load board layout from {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Generates a tile map following `options.generation`, keeping the
+    /// first click and its safe radius clear if already known
+    fn generate_tile_map(
+        options: &BoardOptions,
+        seed: u64,
+        start: Option<(Coordinates, u8)>,
+    ) -> Result<TileMap, PlacementError> {
+        match options.generation {
+            Generation::Random | Generation::FromFile(_) => {
+                let mut tile_map = Self::empty_tile_map(options);
+                match start {
+                    Some((coords, radius)) => {
+                        tile_map.add_bombs_excluding(options.bomb_count, seed, coords, radius)?
                     }
-                    Tile::Empty => (),
+                    None => tile_map.add_bombs(options.bomb_count, seed)?,
+                }
+                Ok(tile_map)
+            }
+            Generation::NoGuess {
+                max_attempts,
+                max_millis,
+            } => {
+                let (tile_map, solvable) = solver::generate_no_guess(
+                    &Self::empty_tile_map(options),
+                    options.bomb_count,
+                    seed,
+                    start,
+                    max_attempts,
+                    Duration::from_millis(max_millis),
+                )?;
+                if !solvable {
+                    log::warn!("No guess free layout found within budget, using a random one");
                 }
+                Ok(tile_map)
+            }
+        }
+    }
+
+    /// Spawns the bomb or bomb counter of `tile` under its tile entity
+    fn spawn_tile_content(
+        cmd: &mut EntityCommands,
+        tile: &Tile,
+        tile_size: f32,
+        tile_padding: f32,
+        board_assets: &BoardAssets,
+    ) {
+        match tile {
+            Tile::Bomb(_) | Tile::AntiBomb(_) => {
+                cmd.insert(Bomb);
+            }
+            Tile::Neighbor(count) => {
+                cmd.insert(Neighbor { count: *count });
             }
+            Tile::Empty | Tile::Hole => (),
+        }
+        Self::spawn_tile_sprites(cmd, tile, tile_size, tile_padding, board_assets);
+    }
+
+    /// Spawns the bomb or bomb counter sprites of `tile` without the gameplay
+    /// components, used for ghost tiles
+    fn spawn_tile_sprites(
+        cmd: &mut EntityCommands,
+        tile: &Tile,
+        tile_size: f32,
+        tile_padding: f32,
+        board_assets: &BoardAssets,
+    ) {
+        match tile {
+            Tile::Bomb(count) | Tile::AntiBomb(count) => {
+                let material = match tile {
+                    Tile::AntiBomb(_) => &board_assets.anti_bomb_material,
+                    _ => &board_assets.bomb_material,
+                };
+                cmd.with_children(|parent| {
+                    parent.spawn(SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::splat(tile_size - tile_padding)),
+                            color: material.color,
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(0., 0., 1.),
+                        texture: material.texture.clone(),
+                        ..default()
+                    });
+                    // stacked mines are labelled with their signed count
+                    if *count > 1 {
+                        let size = (tile_size - tile_padding) / 2.;
+                        let mut text =
+                            Self::bomb_count_text_bundle(tile.mine_value(), board_assets, size);
+                        text.transform = Transform::from_xyz(size / 2., -size / 2., 1.5);
+                        parent.spawn(text);
+                    }
+                });
+            }
+            Tile::Neighbor(count) => {
+                cmd.with_children(|parent| {
+                    parent.spawn(Self::bomb_count_text_bundle(
+                        *count,
+                        board_assets,
+                        tile_size - tile_padding,
+                    ));
+                });
+            }
+            Tile::Empty | Tile::Hole => (),
         }
     }
 
     fn adaptive_tile_size(
         window: &Window,
-        (min, max): (f32, f32),      // Tile size constraints
-        (width, height): (u16, u16), // Tile map dimensions
+        (min, max): (f32, f32), // Tile size constraints
+        unit_size: Vec2,        // Board size with a tile size of 1
     ) -> f32 {
-        let max_w = window.resolution.width() / width as f32;
-        let max_h = window.resolution.height() / height as f32;
+        let max_w = window.resolution.width() / unit_size.x;
+        let max_h = window.resolution.height() / unit_size.y;
         max_w.min(max_h).clamp(min, max)
     }
 
-    fn bomb_count_text_bundle(count: u8, board_assets: &BoardAssets, size: f32) -> Text2dBundle {
+    /// Counter text, counters of zero or less use the negative counter color
+    fn bomb_count_text_bundle(count: i8, board_assets: &BoardAssets, size: f32) -> Text2dBundle {
         // retrieve the text and the correct color
         let color = board_assets.bomb_counter_color(count);
+        let value = count.to_string();
+        // wide neighborhoods can count past 9, shrink to fit the tile
+        let font_size = size / value.len() as f32;
 
         Text2dBundle {
             text: Text {
                 sections: vec![TextSection {
-                    value: count.to_string(),
+                    value,
                     style: TextStyle {
                         color,
                         font: board_assets.bomb_counter_font.clone(),
-                        font_size: size,
+                        font_size,
                     },
                 }],
                 alignment: TextAlignment::Center,
@@ -259,8 +1061,36 @@ impl<T> BoardPlugin<T> {
         }
     }
 
-    fn cleanup(board: Res<Board>, mut commands: Commands) {
+    fn cleanup(board: Res<Board>, hud: Query<Entity, With<Hud>>, mut commands: Commands) {
+        Self::despawn_board(&mut commands, &board, &hud);
+    }
+
+    /// Reads the save file of a `LoadGameEvent` to replace the current
+    /// board, which is despawned when entering the start state and rebuilt
+    /// from the saved game on the next `create_board`
+    fn load_game(mut commands: Commands, mut load_game_rdr: EventReader<LoadGameEvent>) {
+        let Some(LoadGameEvent(path)) = load_game_rdr.read().last() else {
+            return;
+        };
+        let saved = match SavedGame::read(path) {
+            Ok(saved) => saved,
+            Err(e) => {
+                log::error!("Failed to load game from {}: {e}", path.display());
+                return;
+            }
+        };
+
+        log::info!("Loading game from {}", path.display());
+        commands.insert_resource(saved);
+    }
+
+    fn despawn_board(commands: &mut Commands, board: &Board, hud: &Query<Entity, With<Hud>>) {
         commands.entity(board.entity).despawn_recursive();
+        for entity in hud.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
         commands.remove_resource::<Board>();
+        commands.remove_resource::<GameClock>();
+        commands.remove_resource::<MineCounter>();
     }
 }
@@ -0,0 +1,299 @@
+use crate::components::{CustomField, MenuButton, MenuScreen, MenuText};
+use crate::events::NewGameEvent;
+use crate::resources::{
+    BoardAssets, BoardOptions, ChosenDifficulty, Difficulty, DifficultySettings,
+};
+use crate::BoardPlugin;
+
+use bevy::log;
+use bevy::prelude::*;
+
+const BUTTON_COLOR: Color = Color::DARK_GRAY;
+const HOVERED_BUTTON_COLOR: Color = Color::GRAY;
+
+/// New game menu shown while in `menu_state`, picking a difficulty preset
+/// or a custom board before moving to `start_state`
+///
+/// The pick is applied to the `BoardOptions` resource and saved in the
+/// `DifficultySettings` resource when the host inserted one
+pub struct MenuPlugin<T> {
+    pub menu_state: T,
+    pub start_state: T,
+}
+
+/// Custom difficulty edited in the menu
+#[derive(Debug, Clone, Resource)]
+struct CustomDialog {
+    width: u16,
+    height: u16,
+    bombs: u16,
+    error: Option<String>,
+}
+
+impl CustomDialog {
+    fn difficulty(&self) -> Difficulty {
+        Difficulty::Custom {
+            map_size: (self.width, self.height),
+            bomb_count: self.bombs,
+        }
+    }
+
+    fn value(&self, field: CustomField) -> u16 {
+        match field {
+            CustomField::Width => self.width,
+            CustomField::Height => self.height,
+            CustomField::Bombs => self.bombs,
+        }
+    }
+
+    fn adjust(&mut self, field: CustomField, delta: i32) {
+        let value = (self.value(field) as i32 + delta).clamp(1, u16::MAX as i32) as u16;
+        match field {
+            CustomField::Width => self.width = value,
+            CustomField::Height => self.height = value,
+            CustomField::Bombs => self.bombs = value,
+        }
+        self.error = None;
+    }
+}
+
+impl<T: States> Plugin for MenuPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NewGameEvent>()
+            .init_resource::<ChosenDifficulty>()
+            .add_systems(
+                OnEnter(self.menu_state.clone()),
+                Self::spawn_menu.run_if(resource_exists::<BoardOptions>()),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        Self::menu_buttons,
+                        Self::update_texts.run_if(resource_changed::<CustomDialog>()),
+                        Self::highlight_buttons,
+                    )
+                        .chain()
+                        .run_if(resource_exists::<CustomDialog>()),
+                    Self::start_new_game.run_if(resource_exists::<BoardOptions>()),
+                    BoardPlugin::transition_on::<NewGameEvent>(self.start_state.clone()),
+                )
+                    .chain()
+                    .run_if(in_state(self.menu_state.clone())),
+            )
+            .add_systems(OnExit(self.menu_state.clone()), Self::despawn_menu);
+    }
+}
+
+impl<T: States> MenuPlugin<T> {
+    fn spawn_menu(
+        mut commands: Commands,
+        settings: Option<Res<DifficultySettings>>,
+        board_assets: Res<BoardAssets>,
+    ) {
+        let last = settings.map(|settings| settings.difficulty);
+        let (width, height) = last.unwrap_or_default().map_size();
+        commands.insert_resource(CustomDialog {
+            width,
+            height,
+            bombs: last.unwrap_or_default().bomb_count(),
+            error: None,
+        });
+
+        let style = TextStyle {
+            font: board_assets.bomb_counter_font.clone(),
+            font_size: 16.,
+            color: Color::WHITE,
+        };
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.),
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(Name::new("Menu"))
+            .insert(MenuScreen)
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    "New game",
+                    TextStyle {
+                        font_size: 30.,
+                        ..style.clone()
+                    },
+                ));
+                if let Some(last) = last {
+                    parent.spawn(TextBundle::from_section(
+                        format!("Last game: {last}"),
+                        style.clone(),
+                    ));
+                }
+                for preset in Difficulty::PRESETS {
+                    Self::spawn_button(
+                        parent,
+                        preset.to_string(),
+                        MenuButton::Preset(preset),
+                        &style,
+                    );
+                }
+                for (field, label) in [
+                    (CustomField::Width, "Width"),
+                    (CustomField::Height, "Height"),
+                    (CustomField::Bombs, "Bombs"),
+                ] {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(6.),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn(TextBundle::from_section(label, style.clone()));
+                            for delta in [-10, -1] {
+                                Self::spawn_button(
+                                    row,
+                                    format!("{delta}"),
+                                    MenuButton::Adjust(field, delta),
+                                    &style,
+                                );
+                            }
+                            row.spawn(TextBundle::from_section("", style.clone()))
+                                .insert(MenuText::Field(field));
+                            for delta in [1, 10] {
+                                Self::spawn_button(
+                                    row,
+                                    format!("+{delta}"),
+                                    MenuButton::Adjust(field, delta),
+                                    &style,
+                                );
+                            }
+                        });
+                }
+                Self::spawn_button(
+                    parent,
+                    "Custom".to_string(),
+                    MenuButton::StartCustom,
+                    &style,
+                );
+                parent
+                    .spawn(TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            color: Color::RED,
+                            ..style.clone()
+                        },
+                    ))
+                    .insert(MenuText::Error);
+            });
+    }
+
+    fn spawn_button(
+        parent: &mut ChildBuilder,
+        label: String,
+        action: MenuButton,
+        style: &TextStyle,
+    ) {
+        parent
+            .spawn(ButtonBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(6.)),
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            })
+            .insert(action)
+            .with_children(|button| {
+                button.spawn(TextBundle::from_section(label, style.clone()));
+            });
+    }
+
+    fn menu_buttons(
+        options: Res<BoardOptions>,
+        mut dialog: ResMut<CustomDialog>,
+        mut new_game_ewr: EventWriter<NewGameEvent>,
+        buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    ) {
+        for (interaction, button) in buttons.iter() {
+            if *interaction != Interaction::Pressed {
+                continue;
+            }
+            match *button {
+                MenuButton::Preset(difficulty) => new_game_ewr.send(NewGameEvent(difficulty)),
+                MenuButton::Adjust(field, delta) => dialog.adjust(field, delta),
+                MenuButton::StartCustom => {
+                    let difficulty = dialog.difficulty();
+                    let mut custom = options.clone();
+                    difficulty.apply(&mut custom);
+                    match custom.validate() {
+                        Ok(()) => new_game_ewr.send(NewGameEvent(difficulty)),
+                        Err(e) => dialog.error = Some(e.to_string()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies the difficulty of a `NewGameEvent` to the board options and
+    /// keeps it as the last choice
+    fn start_new_game(
+        mut options: ResMut<BoardOptions>,
+        mut chosen: ResMut<ChosenDifficulty>,
+        settings: Option<ResMut<DifficultySettings>>,
+        mut new_game_rdr: EventReader<NewGameEvent>,
+    ) {
+        let Some(NewGameEvent(difficulty)) = new_game_rdr.read().last().copied() else {
+            return;
+        };
+        log::info!("Starting {difficulty} game");
+        difficulty.apply(&mut options);
+        chosen.0 = Some(difficulty);
+        if let Some(mut settings) = settings {
+            settings.difficulty = difficulty;
+            if let Err(e) = settings.save() {
+                log::error!(
+                    "Failed to save settings to {}: {e}",
+                    settings.path.display()
+                );
+            }
+        }
+    }
+
+    fn update_texts(dialog: Res<CustomDialog>, mut texts: Query<(&mut Text, &MenuText)>) {
+        for (mut text, menu_text) in texts.iter_mut() {
+            text.sections[0].value = match menu_text {
+                MenuText::Field(field) => format!("{:>3}", dialog.value(*field)),
+                MenuText::Error => dialog.error.clone().unwrap_or_default(),
+            };
+        }
+    }
+
+    fn highlight_buttons(
+        mut buttons: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
+    ) {
+        for (interaction, mut color) in buttons.iter_mut() {
+            *color = match interaction {
+                Interaction::Hovered | Interaction::Pressed => HOVERED_BUTTON_COLOR,
+                Interaction::None => BUTTON_COLOR,
+            }
+            .into();
+        }
+    }
+
+    fn despawn_menu(mut commands: Commands, screens: Query<Entity, With<MenuScreen>>) {
+        for entity in screens.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        commands.remove_resource::<CustomDialog>();
+    }
+}
@@ -0,0 +1,366 @@
+use crate::components::Coordinates;
+use crate::resources::tile::Tile;
+use crate::resources::tile_map::TileMap;
+use crate::resources::Generation;
+use crate::solver::Solver;
+
+use bevy::utils::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+/// Difficulty metrics of a generated tile map
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BoardMetrics {
+    /// Minimum clicks needed to clear the board without chording
+    pub three_bv: u32,
+    pub openings: u32,
+    pub islands: u32,
+    /// Estimated minimum clicks when flagging and chording
+    pub zini: u32,
+    /// Guesses the solver needs to clear the board, `None` if it ran out of time
+    pub guesses: Option<u32>,
+}
+
+/// Time the solver may spend counting guesses, unless the board was
+/// generated with the budget of `Generation::NoGuess`
+pub const GUESSES_MAX_MILLIS: u64 = 100;
+
+impl BoardMetrics {
+    pub fn new(tile_map: &TileMap, generation: &Generation) -> Self {
+        let max_millis = match generation {
+            Generation::NoGuess { max_millis, .. } => *max_millis,
+            _ => GUESSES_MAX_MILLIS,
+        };
+        Self {
+            three_bv: three_bv(tile_map),
+            openings: openings(tile_map),
+            islands: islands(tile_map),
+            zini: zini(tile_map),
+            guesses: guesses(tile_map, Duration::from_millis(max_millis)),
+        }
+    }
+}
+
+/// Minimum number of clicks needed to clear `tile_map` without chording (3BV)
+///
+/// Every opening, an area of empty tiles and its bordering counters, is one click
+/// and every counter outside of an opening is one more
+pub fn three_bv(tile_map: &TileMap) -> u32 {
+    let lone_counters = tiles(tile_map)
+        .filter(|c| is_lone_counter(tile_map, *c))
+        .count() as u32;
+    openings(tile_map) + lone_counters
+}
+
+/// Number of openings, areas of connected empty tiles uncovered by a single click
+pub fn openings(tile_map: &TileMap) -> u32 {
+    label_openings(tile_map).1
+}
+
+/// Number of islands, groups of adjacent counters outside of the openings
+pub fn islands(tile_map: &TileMap) -> u32 {
+    let mut visited = HashSet::new();
+    let mut islands = 0;
+    for coords in tiles(tile_map) {
+        if !is_lone_counter(tile_map, coords) || !visited.insert(coords) {
+            continue;
+        }
+        islands += 1;
+        let mut stack = vec![coords];
+        while let Some(coords) = stack.pop() {
+            stack.extend(
+                tile_map
+                    .surrounding_tiles(coords)
+                    .filter(|c| is_lone_counter(tile_map, *c) && visited.insert(*c)),
+            );
+        }
+    }
+    islands
+}
+
+/// Greedy estimate of the minimum number of clicks needed to clear `tile_map`
+/// using flags and chords (ZiNi)
+///
+/// Repeatedly chords the counter saving the most clicks over plain clicks,
+/// counting the clicks to uncover it and flag its mines, then clicks every
+/// opening and counter left
+pub fn zini(tile_map: &TileMap) -> u32 {
+    let mut zini = ZiNi::new(tile_map);
+    let mut candidates: BinaryHeap<_> = tiles(tile_map)
+        .map(|c| (zini.premium(c), Reverse(c)))
+        .filter(|(premium, _)| *premium > 0)
+        .collect();
+    while let Some((premium, Reverse(coords))) = candidates.pop() {
+        // premiums change as tiles get uncovered and flagged
+        let current = zini.premium(coords);
+        if current != premium {
+            if current > 0 {
+                candidates.push((current, Reverse(coords)));
+            }
+            continue;
+        }
+        for changed in zini.chord(coords) {
+            candidates.extend(
+                tile_map
+                    .surrounding_tiles(changed)
+                    .map(|c| (zini.premium(c), Reverse(c)))
+                    .filter(|(premium, _)| *premium > 0),
+            );
+        }
+    }
+    zini.clicks_left()
+}
+
+/// Number of guesses the solver needs to clear `tile_map`, starting from its
+/// first empty tile. `None` if solving takes longer than `max_duration`
+pub fn guesses(tile_map: &TileMap, max_duration: Duration) -> Option<u32> {
+    let started = Instant::now();
+    let mut solver = Solver::new(tile_map);
+    let mut guesses = 0;
+    if let Some(start) = tile_map.first_empty_tile() {
+        solver.reveal(start);
+    }
+    while !solver.solve() {
+        if started.elapsed() >= max_duration {
+            return None;
+        }
+        if !solver.guess() {
+            break;
+        }
+        guesses += 1;
+    }
+    Some(guesses)
+}
+
+/// State of the ZiNi estimation
+struct ZiNi<'a> {
+    tile_map: &'a TileMap,
+    /// Opening of every empty tile, row by row
+    openings: Vec<Option<u32>>,
+    opening_count: u32,
+    opened: HashSet<u32>,
+    revealed: Vec<bool>,
+    flagged: Vec<bool>,
+    clicks: u32,
+}
+
+impl<'a> ZiNi<'a> {
+    fn new(tile_map: &'a TileMap) -> Self {
+        let (openings, opening_count) = label_openings(tile_map);
+        let size = openings.len();
+        Self {
+            tile_map,
+            openings,
+            opening_count,
+            opened: HashSet::new(),
+            revealed: vec![false; size],
+            flagged: vec![false; size],
+            clicks: 0,
+        }
+    }
+
+    /// Clicks saved by uncovering, flagging around and chording `coords`
+    /// instead of clicking the openings and counters around it
+    fn premium(&self, coords: Coordinates) -> i64 {
+        if !matches!(self.tile(coords), Tile::Neighbor(_)) {
+            return 0;
+        }
+        let mut openings = HashSet::new();
+        let mut counters = 0;
+        let mut flags = 0;
+        for c in self.tile_map.surrounding_tiles(coords) {
+            if self.revealed[self.index(c)] {
+                continue;
+            }
+            match self.openings[self.index(c)] {
+                Some(opening) if !self.opened.contains(&opening) => {
+                    openings.insert(opening);
+                }
+                _ if is_lone_counter(self.tile_map, c) => counters += 1,
+                _ if !self.flagged[self.index(c)] => flags += self.flag_clicks(c),
+                _ => (),
+            }
+        }
+        // uncovering a counter bordering an opening doesn't clear it
+        let uncover = !self.revealed[self.index(coords)] && !is_lone_counter(self.tile_map, coords);
+        (openings.len() + counters) as i64 - flags as i64 - 1 - uncover as i64
+    }
+
+    /// Uncovers `coords`, flags its mines and chords it, returning the
+    /// uncovered and flagged tiles
+    fn chord(&mut self, coords: Coordinates) -> Vec<Coordinates> {
+        let mut changed = self.reveal(coords);
+        if !changed.is_empty() {
+            self.clicks += 1;
+        }
+        for c in self.tile_map.surrounding_tiles(coords) {
+            if !self.tile_map.is_bomb_at(c) {
+                changed.extend(self.reveal(c));
+            } else if !self.flagged[self.index(c)] {
+                let index = self.index(c);
+                self.flagged[index] = true;
+                self.clicks += self.flag_clicks(c);
+                changed.push(c);
+            }
+        }
+        self.clicks += 1;
+        changed
+    }
+
+    /// Uncovers `coords`, flooding through empty tiles, returning every uncovered tile
+    fn reveal(&mut self, coords: Coordinates) -> Vec<Coordinates> {
+        let mut revealed = vec![];
+        let mut stack = vec![coords];
+        while let Some(coords) = stack.pop() {
+            let index = self.index(coords);
+            if self.revealed[index] {
+                continue;
+            }
+            self.revealed[index] = true;
+            revealed.push(coords);
+            if let Some(opening) = self.openings[index] {
+                self.opened.insert(opening);
+                stack.extend(self.tile_map.surrounding_tiles(coords));
+            }
+        }
+        revealed
+    }
+
+    /// Clicks spent so far plus a click per opening and counter left
+    fn clicks_left(&self) -> u32 {
+        let counters = tiles(self.tile_map)
+            .filter(|c| is_lone_counter(self.tile_map, *c) && !self.revealed[self.index(*c)])
+            .count() as u32;
+        self.clicks + self.opening_count - self.opened.len() as u32 + counters
+    }
+
+    /// Right clicks needed to flag every mine of a tile
+    fn flag_clicks(&self, coords: Coordinates) -> u32 {
+        match self.tile(coords) {
+            Tile::Bomb(count) => *count as u32,
+            // negative flags come after every regular flag
+            Tile::AntiBomb(count) => (self.tile_map.max_mines() + count) as u32,
+            _ => 0,
+        }
+    }
+
+    fn tile(&self, coords: Coordinates) -> &Tile {
+        &self.tile_map[coords.y as usize][coords.x as usize]
+    }
+
+    fn index(&self, coords: Coordinates) -> usize {
+        coords.y as usize * self.tile_map.width() as usize + coords.x as usize
+    }
+}
+
+/// Opening of every empty tile row by row, and the number of openings
+fn label_openings(tile_map: &TileMap) -> (Vec<Option<u32>>, u32) {
+    let width = tile_map.width() as usize;
+    let mut labels = vec![None; width * tile_map.height() as usize];
+    let mut count = 0;
+    for coords in tiles(tile_map) {
+        let index = coords.y as usize * width + coords.x as usize;
+        if tile_map[coords.y as usize][coords.x as usize] != Tile::Empty || labels[index].is_some()
+        {
+            continue;
+        }
+        labels[index] = Some(count);
+        let mut stack = vec![coords];
+        while let Some(coords) = stack.pop() {
+            for c in tile_map.surrounding_tiles(coords) {
+                let index = c.y as usize * width + c.x as usize;
+                if tile_map[c.y as usize][c.x as usize] == Tile::Empty && labels[index].is_none() {
+                    labels[index] = Some(count);
+                    stack.push(c);
+                }
+            }
+        }
+        count += 1;
+    }
+    (labels, count)
+}
+
+/// Return true for counters outside of every opening, each taking a click of its own
+fn is_lone_counter(tile_map: &TileMap, coords: Coordinates) -> bool {
+    matches!(
+        tile_map[coords.y as usize][coords.x as usize],
+        Tile::Neighbor(_)
+    ) && !tile_map
+        .surrounding_tiles(coords)
+        .any(|c| tile_map[c.y as usize][c.x as usize] == Tile::Empty)
+}
+
+fn tiles(tile_map: &TileMap) -> impl Iterator<Item = Coordinates> {
+    let (width, height) = (tile_map.width(), tile_map.height());
+    (0..height).flat_map(move |y| (0..width).map(move |x| Coordinates { x, y }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile_map(width: u16, height: u16, mines: &[(u16, u16)]) -> TileMap {
+        let mut tile_map = TileMap::empty(width, height);
+        let mines: Vec<_> = mines
+            .iter()
+            .map(|(x, y)| Coordinates { x: *x, y: *y })
+            .collect();
+        tile_map.add_bombs_at(&mines);
+        tile_map
+    }
+
+    #[test]
+    fn single_opening() {
+        // . . .
+        // 1 1 .
+        // * 1 .
+        let tile_map = tile_map(3, 3, &[(0, 0)]);
+        assert_eq!(openings(&tile_map), 1);
+        assert_eq!(islands(&tile_map), 0);
+        assert_eq!(three_bv(&tile_map), 1);
+        assert_eq!(zini(&tile_map), 1);
+        assert_eq!(guesses(&tile_map, Duration::ZERO), Some(0));
+    }
+
+    #[test]
+    fn separate_openings() {
+        // . 1 *
+        // 1 2 1
+        // * 1 .
+        let tile_map = tile_map(3, 3, &[(0, 0), (2, 2)]);
+        assert_eq!(openings(&tile_map), 2);
+        assert_eq!(islands(&tile_map), 0);
+        assert_eq!(three_bv(&tile_map), 2);
+        assert_eq!(zini(&tile_map), 2);
+    }
+
+    #[test]
+    fn counter_islands() {
+        // * 1 1 * 1 1 *
+        let tile_map = tile_map(7, 1, &[(0, 0), (3, 0), (6, 0)]);
+        assert_eq!(openings(&tile_map), 0);
+        assert_eq!(islands(&tile_map), 2);
+        assert_eq!(three_bv(&tile_map), 4);
+    }
+
+    #[test]
+    fn chording_saves_clicks() {
+        // 1 1 1
+        // 1 * 1
+        // 1 1 1
+        let tile_map = tile_map(3, 3, &[(1, 1)]);
+        assert_eq!(islands(&tile_map), 1);
+        assert_eq!(three_bv(&tile_map), 8);
+        // two chords of three clicks each, one uncovered by the first
+        assert_eq!(zini(&tile_map), 5);
+    }
+
+    #[test]
+    fn guesses_are_bounded() {
+        // * 1, nothing to deduce from without a guess
+        let tile_map = tile_map(2, 1, &[(0, 0)]);
+        assert_eq!(guesses(&tile_map, Duration::from_secs(60)), Some(1));
+        assert_eq!(guesses(&tile_map, Duration::ZERO), None);
+    }
+}
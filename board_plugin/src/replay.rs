@@ -0,0 +1,274 @@
+use crate::components::ReplayCursor;
+use crate::events::*;
+use crate::resources::{Board, Replay, ReplayAction, ReplayPlayer, ReplayRecorder};
+use crate::systems::input_handling;
+use crate::BoardPlugin;
+
+use bevy::log;
+use bevy::prelude::*;
+use bevy::window::{CursorMoved, PrimaryWindow};
+
+/// Records the inputs on the board and plays replays back through the board events
+///
+/// Must be added along a `BoardPlugin` using the same states
+pub struct ReplayPlugin<T> {
+    pub start_state: T,
+    pub running_state: T,
+}
+
+impl<T: States> Plugin for ReplayPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveReplayEvent>()
+            .add_event::<PlayReplayEvent>()
+            .add_event::<StopReplayEvent>()
+            .add_event::<ReplayControlEvent>()
+            .add_systems(
+                Update,
+                Self::start_recording.run_if(resource_added::<Board>()),
+            )
+            // undo and redo also happen outside of the running state, once
+            // the game is over
+            .add_systems(
+                Update,
+                (
+                    Self::record_history
+                        .after(Self::record_inputs)
+                        .run_if(resource_exists::<ReplayRecorder>()),
+                    Self::advance_history
+                        .run_if(resource_exists::<ReplayPlayer>())
+                        .run_if(not(in_state(self.running_state.clone()))),
+                )
+                    .run_if(resource_exists::<Board>()),
+            )
+            .add_systems(
+                Update,
+                (
+                    // inputs are recorded in the frame they were sent
+                    (
+                        Self::record_inputs.after(input_handling),
+                        Self::record_cursor,
+                    )
+                        .run_if(resource_exists::<ReplayRecorder>()),
+                    (
+                        Self::advance_replay.before(BoardPlugin::<T>::generate_on_first_click),
+                        Self::move_replay_cursor.after(Self::advance_replay),
+                    )
+                        .run_if(resource_exists::<ReplayPlayer>()),
+                )
+                    .run_if(resource_exists::<Board>())
+                    .run_if(in_state(self.running_state.clone())),
+            )
+            .add_systems(
+                Update,
+                (
+                    Self::save_replay.run_if(resource_exists::<ReplayRecorder>()),
+                    Self::play_replay,
+                    Self::control_replay.run_if(resource_exists::<ReplayPlayer>()),
+                    Self::stop_replay,
+                    (
+                        BoardPlugin::enter(self.start_state.clone()),
+                        Self::restart_board,
+                    )
+                        .run_if(Self::needs_restart),
+                )
+                    .chain(),
+            );
+    }
+}
+
+impl<T: States> ReplayPlugin<T> {
+    /// Starts recording every new board
+    fn start_recording(mut commands: Commands, board: Res<Board>) {
+        commands.insert_resource(ReplayRecorder::new(&board));
+    }
+
+    fn record_inputs(
+        time: Res<Time>,
+        mut recorder: ResMut<ReplayRecorder>,
+        mut tile_trigger_rdr: EventReader<TileTriggerEvent>,
+        mut tile_mark_rdr: EventReader<TileMarkEvent>,
+        mut tile_chord_rdr: EventReader<TileChordEvent>,
+    ) {
+        recorder.tick(time.delta());
+        for TileTriggerEvent(coords) in tile_trigger_rdr.read() {
+            recorder.record(ReplayAction::Trigger(*coords));
+        }
+        for TileMarkEvent(coords) in tile_mark_rdr.read() {
+            recorder.record(ReplayAction::Mark(*coords));
+        }
+        for TileChordEvent(coords) in tile_chord_rdr.read() {
+            recorder.record(ReplayAction::Chord(*coords));
+        }
+    }
+
+    fn record_history(
+        mut recorder: ResMut<ReplayRecorder>,
+        mut undo_rdr: EventReader<UndoEvent>,
+        mut redo_rdr: EventReader<RedoEvent>,
+    ) {
+        for _ in undo_rdr.read() {
+            recorder.record(ReplayAction::Undo);
+        }
+        for _ in redo_rdr.read() {
+            recorder.record(ReplayAction::Redo);
+        }
+    }
+
+    /// Records the cursor moves over the board
+    fn record_cursor(
+        board: Res<Board>,
+        windows: Query<&Window, With<PrimaryWindow>>,
+        mut recorder: ResMut<ReplayRecorder>,
+        mut cursor_moved_rdr: EventReader<CursorMoved>,
+    ) {
+        for event in cursor_moved_rdr.read() {
+            let Ok(window) = windows.get(event.window) else {
+                continue;
+            };
+            if let Some(position) = board.board_position(window, event.position) {
+                recorder.record(ReplayAction::Cursor(position / board.tile_size));
+            }
+        }
+    }
+
+    fn save_replay(
+        recorder: Res<ReplayRecorder>,
+        mut save_replay_rdr: EventReader<SaveReplayEvent>,
+    ) {
+        for SaveReplayEvent(path) in save_replay_rdr.read() {
+            match recorder.replay.write(path) {
+                Ok(()) => log::info!("Replay saved to {}", path.display()),
+                Err(e) => log::error!("Failed to save replay to {}: {e}", path.display()),
+            }
+        }
+    }
+
+    /// Reads the replay file of a `PlayReplayEvent`, the board is then
+    /// recreated from it
+    fn play_replay(mut commands: Commands, mut play_replay_rdr: EventReader<PlayReplayEvent>) {
+        let Some(PlayReplayEvent(path)) = play_replay_rdr.read().last() else {
+            return;
+        };
+        match Replay::read(path) {
+            Ok(replay) => {
+                log::info!("Playing replay from {}", path.display());
+                commands.insert_resource(ReplayPlayer::new(replay));
+            }
+            Err(e) => log::error!("Failed to load replay from {}: {e}", path.display()),
+        }
+    }
+
+    fn control_replay(
+        mut player: ResMut<ReplayPlayer>,
+        mut replay_control_rdr: EventReader<ReplayControlEvent>,
+    ) {
+        for event in replay_control_rdr.read() {
+            match *event {
+                ReplayControlEvent::TogglePause => {
+                    let paused = player.is_paused();
+                    player.set_paused(!paused);
+                }
+                ReplayControlEvent::Seek(position) => player.seek(position),
+                ReplayControlEvent::Speed(speed) => player.set_speed(speed),
+            }
+        }
+    }
+
+    fn stop_replay(
+        mut commands: Commands,
+        cursors: Query<Entity, With<ReplayCursor>>,
+        mut stop_replay_rdr: EventReader<StopReplayEvent>,
+    ) {
+        if stop_replay_rdr.read().last().is_none() {
+            return;
+        }
+        log::info!("Stopping replay");
+        commands.remove_resource::<ReplayPlayer>();
+        for entity in cursors.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    /// Sends the recorded inputs due at the playback position through the
+    /// board events
+    fn advance_replay(
+        time: Res<Time>,
+        mut player: ResMut<ReplayPlayer>,
+        mut tile_trigger_ewr: EventWriter<TileTriggerEvent>,
+        mut tile_mark_ewr: EventWriter<TileMarkEvent>,
+        mut tile_chord_ewr: EventWriter<TileChordEvent>,
+        mut undo_ewr: EventWriter<UndoEvent>,
+        mut redo_ewr: EventWriter<RedoEvent>,
+    ) {
+        player.tick(time.delta());
+        for action in player.due_events() {
+            match action {
+                ReplayAction::Trigger(coords) => tile_trigger_ewr.send(TileTriggerEvent(coords)),
+                ReplayAction::Mark(coords) => tile_mark_ewr.send(TileMarkEvent(coords)),
+                ReplayAction::Chord(coords) => tile_chord_ewr.send(TileChordEvent(coords)),
+                ReplayAction::Undo => undo_ewr.send(UndoEvent),
+                ReplayAction::Redo => redo_ewr.send(RedoEvent),
+                ReplayAction::Cursor(_) => (),
+            }
+        }
+    }
+
+    /// Sends the undo and redo events due while the host is out of the
+    /// running state, the playback time only advances while running
+    fn advance_history(
+        mut player: ResMut<ReplayPlayer>,
+        mut undo_ewr: EventWriter<UndoEvent>,
+        mut redo_ewr: EventWriter<RedoEvent>,
+    ) {
+        for action in player.due_history_events() {
+            match action {
+                ReplayAction::Undo => undo_ewr.send(UndoEvent),
+                ReplayAction::Redo => redo_ewr.send(RedoEvent),
+                _ => (),
+            }
+        }
+    }
+
+    /// Moves the replay cursor to the last played cursor position
+    fn move_replay_cursor(
+        mut commands: Commands,
+        board: Res<Board>,
+        player: Res<ReplayPlayer>,
+        mut cursors: Query<&mut Transform, With<ReplayCursor>>,
+    ) {
+        let Some(position) = player.cursor() else {
+            return;
+        };
+        let translation = (position * board.tile_size).extend(10.);
+        match cursors.get_single_mut() {
+            Ok(mut transform) => transform.translation = translation,
+            Err(_) => {
+                commands.entity(board.entity).with_children(|parent| {
+                    parent
+                        .spawn(SpriteBundle {
+                            sprite: Sprite {
+                                color: Color::rgba(1., 1., 1., 0.8),
+                                custom_size: Some(Vec2::splat(board.tile_size / 4.)),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(translation),
+                            ..default()
+                        })
+                        .insert(Name::new("Replay cursor"))
+                        .insert(ReplayCursor);
+                });
+            }
+        }
+    }
+
+    /// Restarts the playback once it needs a fresh board, the current board
+    /// is despawned when entering the start state and the next one is created
+    /// from the replay options
+    fn restart_board(mut player: ResMut<ReplayPlayer>) {
+        player.restarted();
+    }
+
+    fn needs_restart(player: Option<Res<ReplayPlayer>>) -> bool {
+        player.is_some_and(|player| player.needs_restart())
+    }
+}
@@ -1,25 +1,78 @@
-use crate::{Bounds2, Coordinates, TileMap};
+use crate::metrics::BoardMetrics;
+use crate::resources::{BoardAction, History, MarkChange};
+use crate::{BoardOptions, Bounds2, Coordinates, GameCore, GameStatus, TileMap, TileMark};
 
 use bevy::prelude::*;
 use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+/// Clicks that changed the board, by kind, undone clicks excluded
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ClickCounts {
+    pub left: u32,
+    pub right: u32,
+    pub chord: u32,
+}
+
+impl ClickCounts {
+    pub fn add(&mut self, other: Self) {
+        self.left += other.left;
+        self.right += other.right;
+        self.chord += other.chord;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.left = self.left.saturating_sub(other.left);
+        self.right = self.right.saturating_sub(other.right);
+        self.chord = self.chord.saturating_sub(other.chord);
+    }
+}
 
 #[derive(Debug, Resource)]
 pub struct Board {
-    pub tile_map: TileMap,
+    pub core: GameCore,
     pub bounds: Bounds2,
     pub tile_size: f32,
     pub covered_tiles: HashMap<Coordinates, Entity>,
-    pub marked_tiles: Vec<Coordinates>,
     pub entity: Entity,
+    /// Seed used to generate the tile map, can be fed back in `BoardOptions::seed`
+    pub seed: u64,
+    /// Options the board was created with
+    pub options: BoardOptions,
+    /// False until the bombs are placed, when generation waits for the first click
+    pub generated: bool,
+    pub clicks: ClickCounts,
+    /// True when the board was restored from a `SavedGame`
+    pub restored: bool,
+    /// True once the game was counted in the `PlayerStats`, finishing it
+    /// again after an undo doesn't count
+    pub recorded: bool,
+    /// True once a win was submitted to the `Leaderboard`
+    pub ranked: bool,
+    /// Undo history, `None` when disabled by `BoardOptions::history`
+    pub history: Option<History>,
+    /// Difficulty metrics of the tile map, `None` until the bombs are placed
+    pub metrics: Option<BoardMetrics>,
 }
 
 impl Board {
     /// Translates a mouse position to board coordinates
-    pub fn mouse_position(&self, window: &Window, mut position: Vec2) -> Option<Coordinates> {
+    pub fn mouse_position(&self, window: &Window, position: Vec2) -> Option<Coordinates> {
+        let position = self.board_position(window, position)?;
+        let tile_map = self.tile_map();
+        tile_map
+            .topology()
+            .coordinates_at(position, self.tile_size)
+            .filter(|c| c.x < tile_map.width() && c.y < tile_map.height())
+            .filter(|c| !tile_map.is_hole(*c))
+    }
+
+    /// Translates a mouse position to a position relative to the bottom left of the board
+    pub fn board_position(&self, window: &Window, mut position: Vec2) -> Option<Vec2> {
         // Window to world space
         let window_size = Vec2::new(window.width(), window.height());
         position.y = window_size.y - position.y;
-        position = position - window_size / 2.;
+        position -= window_size / 2.;
 
         // Bounds check
         if !self.bounds.in_bounds(position) {
@@ -27,67 +80,168 @@ impl Board {
         }
 
         // World space to board space
-        let coordinates = position - self.bounds.position;
-        Some(Coordinates {
-            x: (coordinates.x / self.tile_size) as u16,
-            y: (coordinates.y / self.tile_size) as u16,
-        })
+        Some(position - self.bounds.position)
     }
 
-    /// Retrieves a covered tile entity
-    pub fn tile_to_uncover(&self, coords: &Coordinates) -> Option<&Entity> {
-        if self.marked_tiles.contains(coords) {
-            None
-        } else {
-            self.covered_tiles.get(coords)
+    pub fn tile_map(&self) -> &TileMap {
+        self.core.tile_map()
+    }
+
+    /// Uncovers a tile, returning the cover entities of every uncovered tile
+    pub fn reveal(&mut self, coords: &Coordinates) -> Vec<Entity> {
+        let revealed = self.core.reveal(*coords);
+        let click = ClickCounts {
+            left: 1,
+            ..default()
+        };
+        self.record_uncovered(&revealed, click);
+        self.take_covers(&revealed)
+    }
+
+    /// Uncovers the neighbors of a satisfied counter, returning their cover entities
+    pub fn chord(&mut self, coords: &Coordinates) -> Vec<Entity> {
+        let revealed = self.core.chord(*coords);
+        let click = ClickCounts {
+            chord: 1,
+            ..default()
+        };
+        self.record_uncovered(&revealed, click);
+        self.take_covers(&revealed)
+    }
+
+    /// Uncovers the remaining bombs of a lost game, returning their cover entities
+    pub fn reveal_bombs(&mut self) -> Vec<Entity> {
+        let bombs = self.core.reveal_bombs();
+        if let Some(history) = &mut self.history {
+            history.extend_last(BoardAction {
+                uncovered: bombs.clone(),
+                ..default()
+            });
         }
+        self.take_covers(&bombs)
     }
 
-    /// Try to uncover a tile, returning an Entity
-    pub fn try_uncover_tile(&mut self, coords: &Coordinates) -> Option<Entity> {
-        if self.marked_tiles.contains(coords) {
-            self.unmark_tile(coords)?;
+    /// Flags the remaining bombs of a won game, returning their cover entities
+    /// and new marks
+    pub fn flag_bombs(&mut self) -> Vec<(Entity, TileMark)> {
+        let bombs = self.core.flag_bombs();
+        let marks = bombs
+            .iter()
+            .map(|(coords, before)| MarkChange {
+                coords: *coords,
+                before: *before,
+                after: self.core.mark(coords),
+            })
+            .collect();
+        if let Some(history) = &mut self.history {
+            history.extend_last(BoardAction { marks, ..default() });
         }
-        self.covered_tiles.remove(coords)
+        bombs
+            .iter()
+            .filter_map(|(c, _)| Some((*self.covered_tiles.get(c)?, self.core.mark(c)?)))
+            .collect()
     }
 
-    /// Retrieve the adjacent covered tile entities of `coord`
-    pub fn adjacent_covered_tiles(&self, coords: &Coordinates) -> Vec<Entity> {
-        self.tile_map
-            .surrounding_tiles(*coords)
-            .filter_map(|c| self.tile_to_uncover(&c))
+    /// Retrieves the cover entities of flagged tiles without a bomb
+    pub fn wrong_flags(&self) -> Vec<Entity> {
+        self.core
+            .wrong_flags()
+            .filter_map(|c| self.covered_tiles.get(c))
             .copied()
             .collect()
     }
 
-    /// Removes the `coords` from `marked_tiles`
-    fn unmark_tile(&mut self, coords: &Coordinates) -> Option<Coordinates> {
-        let pos = match self.marked_tiles.iter().position(|a| a == coords) {
-            Some(p) => p,
-            None => {
-                error!("Failed to unmark tile at {coords}");
-                return None;
-            }
-        };
+    /// Coordinates of the flagged tiles
+    pub fn marked_tiles(&self) -> impl Iterator<Item = &Coordinates> {
+        self.core.flagged_tiles()
+    }
 
-        Some(self.marked_tiles.remove(pos))
+    /// Mines of either sign left to flag, computed from the generated bomb
+    /// counts if any
+    pub fn remaining_mines(&self) -> i32 {
+        let bombs = if self.generated {
+            self.tile_map().bomb_count() as i32 + self.tile_map().anti_bomb_count() as i32
+        } else {
+            self.options.bomb_count as i32 + self.options.anti_bomb_count as i32
+        };
+        bombs - self.core.flag_count() as i32
     }
 
     /// Return true if the board is complete
     pub fn is_completed(&self) -> bool {
-        self.tile_map.bomb_count() as usize == self.covered_tiles.len()
+        self.core.status() == GameStatus::Won
     }
 
-    /// Try to mark or unmark a tile, returning the entity and marked status of tile
-    pub fn try_toggle_mark(&mut self, coords: &Coordinates) -> Option<(Entity, bool)> {
+    /// Try to cycle the mark of a tile, returning the entity and new mark of tile
+    pub fn try_toggle_mark(&mut self, coords: &Coordinates) -> Option<(Entity, Option<TileMark>)> {
         let entity = *self.covered_tiles.get(coords)?;
-        let mark = if self.marked_tiles.contains(coords) {
-            self.unmark_tile(coords)?;
-            false
-        } else {
-            self.marked_tiles.push(*coords);
-            true
+        let before = self.core.mark(coords);
+        let mark = self.core.cycle_mark(*coords, self.options.question_marks)?;
+        let click = ClickCounts {
+            right: 1,
+            ..default()
         };
+        self.clicks.add(click);
+        if let Some(history) = &mut self.history {
+            history.record(BoardAction {
+                marks: vec![MarkChange {
+                    coords: *coords,
+                    before,
+                    after: mark,
+                }],
+                clicks: click,
+                ..default()
+            });
+        }
         Some((entity, mark))
     }
+
+    /// Reverts the last action on the game, the uncovered tiles are left
+    /// without cover entities
+    pub fn undo(&mut self) -> Option<BoardAction> {
+        let action = self.history.as_mut()?.undo()?;
+        self.core.cover(&action.uncovered);
+        for change in action.marks.iter().rev() {
+            self.core.set_mark(change.coords, change.before);
+        }
+        self.clicks.remove(action.clicks);
+        Some(action)
+    }
+
+    /// Applies the last reverted action again, returning it with the
+    /// cover entities of the uncovered tiles
+    pub fn redo(&mut self) -> Option<(BoardAction, Vec<Entity>)> {
+        let action = self.history.as_mut()?.redo()?;
+        self.core.uncover(&action.uncovered);
+        for change in action.marks.iter() {
+            self.core.set_mark(change.coords, change.after);
+        }
+        self.clicks.add(action.clicks);
+        let covers = self.take_covers(&action.uncovered);
+        Some((action, covers))
+    }
+
+    /// Counts the `click` uncovering `coords` and records it, clicks
+    /// uncovering nothing aren't counted
+    fn record_uncovered(&mut self, coords: &[Coordinates], click: ClickCounts) {
+        if coords.is_empty() {
+            return;
+        }
+        self.clicks.add(click);
+        if let Some(history) = &mut self.history {
+            history.record(BoardAction {
+                uncovered: coords.to_vec(),
+                clicks: click,
+                ..default()
+            });
+        }
+    }
+
+    /// Removes the cover entities of `coords` from `covered_tiles`
+    fn take_covers(&mut self, coords: &[Coordinates]) -> Vec<Entity> {
+        coords
+            .iter()
+            .filter_map(|c| self.covered_tiles.remove(c))
+            .collect()
+    }
 }
@@ -1,3 +1,5 @@
+use crate::resources::HexOrientation;
+use crate::TileMark;
 use bevy::prelude::*;
 
 /// Material of a `Sprite` with a texture and color
@@ -18,7 +20,7 @@ impl Default for SpriteMaterial {
 
 /// Assets for the board. Must be used as a resource
 ///
-/// Use the loader for partial setup
+/// Can be loaded from a `.theme.ron` file, see `BoardFilesPlugin`
 #[derive(Debug, Clone, Resource)]
 pub struct BoardAssets {
     pub label: String,
@@ -28,7 +30,18 @@ pub struct BoardAssets {
     pub bomb_counter_font: Handle<Font>,
     pub bomb_counter_colors: Vec<Color>,
     pub flag_material: SpriteMaterial,
+    /// Flag of the tiles believed to hide negative mines
+    pub anti_flag_material: SpriteMaterial,
+    pub wrong_flag_material: SpriteMaterial,
+    pub question_material: SpriteMaterial,
     pub bomb_material: SpriteMaterial,
+    pub anti_bomb_material: SpriteMaterial,
+    /// Color of the counters of zero or less
+    pub negative_counter_color: Color,
+    /// Hexagon masks replacing the tile and cover textures of hexagonal
+    /// boards, hexagonal tiles keep the material textures without them
+    pub pointy_hex_texture: Option<Handle<Image>>,
+    pub flat_hex_texture: Option<Handle<Image>>,
 }
 
 impl BoardAssets {
@@ -44,7 +57,10 @@ impl BoardAssets {
     }
 
     /// Safely retrieves the color matching a bomb counter
-    pub fn bomb_counter_color(&self, counter: u8) -> Color {
+    pub fn bomb_counter_color(&self, counter: i8) -> Color {
+        if counter <= 0 {
+            return self.negative_counter_color;
+        }
         let counter = counter.saturating_sub(1) as usize;
         match self.bomb_counter_colors.get(counter) {
             Some(c) => *c,
@@ -54,4 +70,21 @@ impl BoardAssets {
             },
         }
     }
+
+    /// Hexagon mask of tiles with the given orientation, if any
+    pub fn hex_texture(&self, orientation: HexOrientation) -> Option<&Handle<Image>> {
+        match orientation {
+            HexOrientation::Pointy => self.pointy_hex_texture.as_ref(),
+            HexOrientation::Flat => self.flat_hex_texture.as_ref(),
+        }
+    }
+
+    /// Sprite material of a tile mark
+    pub fn mark_material(&self, mark: TileMark) -> &SpriteMaterial {
+        match mark {
+            TileMark::Flag(_) => &self.flag_material,
+            TileMark::AntiFlag(_) => &self.anti_flag_material,
+            TileMark::Question => &self.question_material,
+        }
+    }
 }
@@ -0,0 +1,19 @@
+use crate::resources::{BoardAssets, BoardOptions};
+
+use bevy::prelude::*;
+
+/// Board assets read from a `.theme.ron` file
+#[derive(Debug, Clone, Asset, TypePath)]
+pub struct BoardTheme {
+    pub assets: BoardAssets,
+}
+
+/// Option and theme files followed by `BoardFilesPlugin`, replacing the
+/// `BoardOptions` and `BoardAssets` resources once loaded or edited
+#[derive(Debug, Clone, Default, Resource)]
+pub struct BoardFiles {
+    /// Handle of a `.board.ron` file
+    pub options: Option<Handle<BoardOptions>>,
+    /// Handle of a `.theme.ron` file
+    pub theme: Option<Handle<BoardTheme>>,
+}
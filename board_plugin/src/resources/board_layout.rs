@@ -0,0 +1,370 @@
+use crate::components::Coordinates;
+use crate::resources::{Tile, TileMap};
+use crate::{GameCore, TileMark};
+
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+
+/// Hand authored mine layout, with an optional game state
+///
+/// Written as ASCII rows from top to bottom, `*` marking mines, `+` negative
+/// mines and `.` safe tiles, or as JSON which also holds the revealed and
+/// flagged tiles
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct BoardLayout {
+    pub width: u16,
+    pub height: u16,
+    /// Mined tiles, repeated once per mine
+    pub mines: Vec<Coordinates>,
+    /// Tiles hiding negative mines, repeated once per mine
+    #[serde(default)]
+    pub anti_mines: Vec<Coordinates>,
+    #[serde(default)]
+    pub revealed: Vec<Coordinates>,
+    /// Flagged tiles, repeated once per flag
+    #[serde(default)]
+    pub flags: Vec<Coordinates>,
+    /// Tiles flagged as negative mines, repeated once per flag
+    #[serde(default)]
+    pub anti_flags: Vec<Coordinates>,
+}
+
+/// Failure to read or write a board layout
+#[derive(Debug)]
+pub enum LayoutError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    InvalidCharacter(char),
+    UnevenRows,
+    TooLarge,
+    Empty,
+    OutOfBounds(Coordinates),
+    /// A tile hides mines of both signs
+    MixedMines(Coordinates),
+    /// A tile is flagged with both signs
+    MixedFlags(Coordinates),
+    /// A mine is already uncovered
+    RevealedMine(Coordinates),
+    /// An uncovered tile is flagged
+    RevealedFlag(Coordinates),
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "layout file access failed: {e}"),
+            Self::Json(e) => write!(f, "invalid JSON layout: {e}"),
+            Self::InvalidCharacter(c) => {
+                write!(
+                    f,
+                    "invalid layout character {c:?}, expected '*', '+' or '.'"
+                )
+            }
+            Self::UnevenRows => write!(f, "layout rows have different lengths"),
+            Self::TooLarge => write!(f, "layout is larger than {} tiles per side", u16::MAX),
+            Self::Empty => write!(f, "layout has no tile"),
+            Self::OutOfBounds(c) => write!(f, "layout tile {c} is out of the board"),
+            Self::MixedMines(c) => write!(f, "layout tile {c} hides mines of both signs"),
+            Self::MixedFlags(c) => write!(f, "layout tile {c} is flagged with both signs"),
+            Self::RevealedMine(c) => write!(f, "layout mine {c} is uncovered"),
+            Self::RevealedFlag(c) => write!(f, "layout tile {c} is both uncovered and flagged"),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+impl BoardLayout {
+    /// Captures the mines of `tile_map`
+    pub fn new(tile_map: &TileMap) -> Self {
+        let mines = |anti: bool| {
+            Self::tiles(tile_map.width(), tile_map.height())
+                .filter(|c| tile_map[c.y as usize][c.x as usize].is_anti_bomb() == anti)
+                .flat_map(|c| std::iter::repeat_n(c, tile_map.mines_at(c) as usize))
+                .collect()
+        };
+        Self {
+            width: tile_map.width(),
+            height: tile_map.height(),
+            mines: mines(false),
+            anti_mines: mines(true),
+            ..Default::default()
+        }
+    }
+
+    /// Captures the mines, revealed tiles and flags of a game
+    pub fn from_game(core: &GameCore) -> Self {
+        let mut revealed: Vec<_> = core.revealed_tiles().collect();
+        revealed.sort();
+        let flags = |anti: bool| {
+            let mut flags: Vec<_> = core
+                .marks()
+                .filter(|(_, mark)| match mark {
+                    TileMark::Flag(_) => !anti,
+                    TileMark::AntiFlag(_) => anti,
+                    TileMark::Question => false,
+                })
+                .flat_map(|(c, mark)| std::iter::repeat_n(*c, mark.flag_count() as usize))
+                .collect();
+            flags.sort();
+            flags
+        };
+        Self {
+            revealed,
+            flags: flags(false),
+            anti_flags: flags(true),
+            ..Self::new(core.tile_map())
+        }
+    }
+
+    /// Parses ASCII rows from top to bottom, `*` marking mines, `+` negative
+    /// mines and `.` safe tiles
+    pub fn from_ascii(text: &str) -> Result<Self, LayoutError> {
+        let rows = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.chars()
+                    .map(|c| match c {
+                        '*' => Ok(Tile::Bomb(1)),
+                        '+' => Ok(Tile::AntiBomb(1)),
+                        '.' => Ok(Tile::Empty),
+                        c => Err(LayoutError::InvalidCharacter(c)),
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<Tile>>, _>>()?;
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 {
+            return Err(LayoutError::Empty);
+        }
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(LayoutError::UnevenRows);
+        }
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(rows.len())) else {
+            return Err(LayoutError::TooLarge);
+        };
+        let mines = |tile: Tile| {
+            Self::tiles(width, height)
+                .filter(|c| rows[(height - 1 - c.y) as usize][c.x as usize] == tile)
+                .collect()
+        };
+        let layout = Self {
+            width,
+            height,
+            mines: mines(Tile::Bomb(1)),
+            anti_mines: mines(Tile::AntiBomb(1)),
+            ..Default::default()
+        };
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    /// ASCII rows from top to bottom, the game state and stacked mines are
+    /// left out
+    pub fn to_ascii(&self) -> String {
+        (0..self.height)
+            .rev()
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| Coordinates { x, y })
+                    .map(
+                        |c| match (self.mines.contains(&c), self.anti_mines.contains(&c)) {
+                            (true, _) => '*',
+                            (false, true) => '+',
+                            (false, false) => '.',
+                        },
+                    )
+                    .chain(std::iter::once('\n'))
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, LayoutError> {
+        let layout: Self = serde_json::from_str(text).map_err(LayoutError::Json)?;
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    pub fn to_json(&self) -> Result<String, LayoutError> {
+        serde_json::to_string_pretty(self).map_err(LayoutError::Json)
+    }
+
+    /// Reads a JSON layout if `path` has a `json` extension, an ASCII layout otherwise
+    pub fn load(path: &Path) -> Result<Self, LayoutError> {
+        let content = std::fs::read_to_string(path).map_err(LayoutError::Io)?;
+        match Self::is_json(path) {
+            true => Self::from_json(&content),
+            false => Self::from_ascii(&content),
+        }
+    }
+
+    /// Writes a JSON layout if `path` has a `json` extension, an ASCII layout otherwise
+    pub fn write(&self, path: &Path) -> Result<(), LayoutError> {
+        let content = match Self::is_json(path) {
+            true => self.to_json()?,
+            false => self.to_ascii(),
+        };
+        std::fs::write(path, content).map_err(LayoutError::Io)
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Checks the layout fits its size and its game state can happen
+    pub fn validate(&self) -> Result<(), LayoutError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(LayoutError::Empty);
+        }
+        let out_of_bounds = self
+            .mines
+            .iter()
+            .chain(self.anti_mines.iter())
+            .chain(self.revealed.iter())
+            .chain(self.flags.iter())
+            .chain(self.anti_flags.iter())
+            .find(|c| c.x >= self.width || c.y >= self.height);
+        if let Some(coords) = out_of_bounds {
+            return Err(LayoutError::OutOfBounds(*coords));
+        }
+        if let Some(coords) = self.anti_mines.iter().find(|c| self.mines.contains(c)) {
+            return Err(LayoutError::MixedMines(*coords));
+        }
+        if let Some(coords) = self.anti_flags.iter().find(|c| self.flags.contains(c)) {
+            return Err(LayoutError::MixedFlags(*coords));
+        }
+        let mut mines = self.mines.iter().chain(self.anti_mines.iter());
+        if let Some(coords) = mines.find(|c| self.revealed.contains(c)) {
+            return Err(LayoutError::RevealedMine(*coords));
+        }
+        let mut flags = self.flags.iter().chain(self.anti_flags.iter());
+        if let Some(coords) = flags.find(|c| self.revealed.contains(c)) {
+            return Err(LayoutError::RevealedFlag(*coords));
+        }
+        Ok(())
+    }
+
+    /// Places the mines on `template`, an empty tile map of the layout size
+    /// giving the topology and neighborhood
+    pub fn tile_map(&self, mut template: TileMap) -> Result<TileMap, LayoutError> {
+        self.validate()?;
+        template.add_bombs_at(&self.mines);
+        template.add_anti_bombs_at(&self.anti_mines);
+        Ok(template)
+    }
+
+    /// Marks of the flagged tiles
+    pub fn marks(&self) -> impl Iterator<Item = (Coordinates, TileMark)> {
+        let flags = self.flags.iter().map(|c| (c, false));
+        let anti_flags = self.anti_flags.iter().map(|c| (c, true));
+        let mut marks: HashMap<Coordinates, TileMark> = HashMap::new();
+        for (coords, anti) in flags.chain(anti_flags) {
+            let count = marks.get(coords).map_or(0, TileMark::flag_count) + 1;
+            let mark = match anti {
+                true => TileMark::AntiFlag(count),
+                false => TileMark::Flag(count),
+            };
+            marks.insert(*coords, mark);
+        }
+        marks.into_iter()
+    }
+
+    fn tiles(width: u16, height: u16) -> impl Iterator<Item = Coordinates> {
+        (0..height).flat_map(move |y| (0..width).map(move |x| Coordinates { x, y }))
+    }
+
+    fn is_json(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(x: u16, y: u16) -> Coordinates {
+        Coordinates { x, y }
+    }
+
+    fn layout() -> BoardLayout {
+        BoardLayout {
+            width: 3,
+            height: 2,
+            mines: vec![coords(0, 1)],
+            anti_mines: vec![coords(2, 1)],
+            revealed: vec![coords(0, 0), coords(1, 0)],
+            flags: vec![coords(0, 1)],
+            anti_flags: vec![coords(2, 1)],
+        }
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        let text = "*.+\n...\n";
+        let layout = BoardLayout::from_ascii(text).unwrap();
+
+        assert_eq!(layout.mines, [coords(0, 1)]);
+        assert_eq!(layout.anti_mines, [coords(2, 1)]);
+        assert_eq!(layout.to_ascii(), text);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let layout = layout();
+        let json = layout.to_json().unwrap();
+
+        assert_eq!(BoardLayout::from_json(&json).unwrap(), layout);
+    }
+
+    #[test]
+    fn game_round_trip() {
+        let layout = layout();
+        let tile_map = layout.tile_map(TileMap::empty(3, 2)).unwrap();
+        let core = GameCore::restore(tile_map, &layout.revealed, layout.marks());
+
+        assert_eq!(BoardLayout::from_game(&core), layout);
+    }
+
+    #[test]
+    fn invalid_layouts() {
+        let invalid = [
+            (BoardLayout::default(), LayoutError::Empty),
+            (
+                BoardLayout {
+                    revealed: vec![coords(3, 0)],
+                    ..layout()
+                },
+                LayoutError::OutOfBounds(coords(3, 0)),
+            ),
+            (
+                BoardLayout {
+                    revealed: vec![coords(0, 1)],
+                    ..layout()
+                },
+                LayoutError::RevealedMine(coords(0, 1)),
+            ),
+            (
+                BoardLayout {
+                    flags: vec![coords(0, 0)],
+                    ..layout()
+                },
+                LayoutError::RevealedFlag(coords(0, 0)),
+            ),
+        ];
+        for (layout, error) in invalid {
+            let json = serde_json::to_string(&layout).unwrap();
+            let result = BoardLayout::from_json(&json).unwrap_err();
+            assert_eq!(result.to_string(), error.to_string());
+        }
+        assert!(matches!(
+            BoardLayout::from_ascii("*+\n*"),
+            Err(LayoutError::UnevenRows)
+        ));
+    }
+}
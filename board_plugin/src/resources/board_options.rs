@@ -1,5 +1,10 @@
+use crate::resources::{MaskError, MaskSource, Neighborhood, PlacementError, ShapeMask, Topology};
+
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
 
 // #[cfg(feature = "debug")]
 // use bevy_inspector_egui::prelude::*;
@@ -44,20 +49,398 @@ impl Default for BoardPosition {
     }
 }
 
-// Use serde to allow saving/loading option presets
+/// Protection against hitting a bomb on the first move
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+pub enum SafeStart {
+    /// Bombs can be anywhere, including under the first click
+    #[default]
+    Disabled,
+    /// Uncovers the first empty tile of the generated map
+    FirstEmpty,
+    /// Bombs are generated on the first click, keeping every tile
+    /// within `radius` of the clicked tile clear
+    FirstClick { radius: u8 },
+}
+
+impl SafeStart {
+    /// Most tiles kept clear of bombs around the first click, reached in
+    /// `radius` steps between the neighbors of `topology` and `neighborhood`
+    ///
+    /// Counting stops once past the `u16::MAX` tiles a board can have
+    pub fn safe_tiles(&self, topology: &Topology, neighborhood: &Neighborhood) -> u32 {
+        let radius = match self {
+            Self::FirstClick { radius } => *radius as u32,
+            Self::Disabled | Self::FirstEmpty => return 0,
+        };
+        if topology.neighbor_offsets(Default::default()).is_some() {
+            // hexagonal rings of 6, 12, 18... tiles
+            return 1 + 3 * radius * (radius + 1);
+        }
+        let offsets: Vec<_> = neighborhood.offsets().collect();
+        let mut safe = HashSet::from([(0, 0)]);
+        let mut frontier = vec![(0, 0)];
+        for _ in 0..radius {
+            if frontier.is_empty() || safe.len() > u16::MAX as usize {
+                break;
+            }
+            frontier = frontier
+                .iter()
+                .flat_map(|&(x, y)| {
+                    offsets
+                        .iter()
+                        .map(move |&(dx, dy)| (x + dx as i32, y + dy as i32))
+                })
+                .filter(|coords| safe.insert(*coords))
+                .collect();
+        }
+        safe.len() as u32
+    }
+}
+
+/// Bomb layout generation strategy
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub enum Generation {
+    /// Bombs are placed at random
+    #[default]
+    Random,
+    /// Layouts are regenerated until the solver clears one without guessing,
+    /// falling back to a random layout once the budget is spent
+    NoGuess { max_attempts: u32, max_millis: u64 },
+    /// Layout read from a `BoardLayout` file, whose size replaces `map_size`.
+    /// Falls back to a random layout if the file can't be read
+    FromFile(PathBuf),
+}
+
+/// Mouse bindings triggering a chord
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct ChordBindings {
+    pub middle_click: bool,
+    /// Left and right buttons pressed together
+    pub left_right_click: bool,
+}
+
+impl Default for ChordBindings {
+    fn default() -> Self {
+        Self {
+            middle_click: true,
+            left_right_click: true,
+        }
+    }
+}
+
+// Use serde to load option presets from `.board.ron` files, see `BoardFilesPlugin`.
+// Missing fields take their `Default` value
 // #[cfg_attr(
 //     feature = "debug",
 //     derive(Reflect, InspectorOptions),
 //     reflect(InspectorOptions)
 // )]
-#[derive(Debug, Clone, Deserialize, Serialize, Resource)]
+#[derive(Debug, Clone, Deserialize, Serialize, Resource, Asset, TypePath)]
+#[serde(default)]
 pub struct BoardOptions {
     pub map_size: (u16, u16),
     pub bomb_count: u16,
     pub position: BoardPosition,
     pub tile_size: TileSize,
     pub tile_padding: f32,
-    pub safe_start: bool, // generate a safe place to start
+    pub safe_start: SafeStart, // generate a safe place to start
+    pub seed: Option<u64>,     // fixed generation seed, random if `None`
+    pub generation: Generation,
+    pub chord_bindings: ChordBindings,
+    pub hud: bool,            // display the mine counter and clock above the board
+    pub history: bool,        // allow undo and redo, ranked apart
+    pub question_marks: bool, // right click cycles flag, question mark and no mark
+    pub topology: Topology,
+    pub neighborhood: Neighborhood, // tiles counted around square tiles
+    pub mask: Option<MaskSource>,   // irregular board shape, overrides `map_size`
+    pub max_mines_per_tile: u8,     // mines a single tile can hide, `bomb_count` counts mines
+    pub anti_bomb_count: u16,       // negative mines subtracting from the counters around them
+}
+
+/// Invalid board options, refused by `BoardOptions::validate`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BoardOptionsError {
+    ZeroSize,
+    /// The board has more tiles than fit in a `u16`
+    TooLarge {
+        tiles: u32,
+    },
+    /// More mines than the tiles outside of the safe start area can hold
+    TooManyBombs {
+        bombs: u32,
+        max: u32,
+    },
+    /// More negative mines than the tiles left clear of regular mines can hold
+    TooManyAntiBombs {
+        anti_bombs: u32,
+        max: u32,
+    },
+    NegativePadding(f32),
+    /// The padding is as large as the tiles, leaving nothing to display
+    PaddingTooLarge {
+        padding: f32,
+        tile_size: f32,
+    },
+    /// `Generation::NoGuess` without a safe start, leaving no known tile to solve from
+    NoGuessWithoutSafeStart,
+    /// `Neighborhood::Radius` beyond `Neighborhood::MAX_RADIUS`
+    RadiusTooLarge(u8),
+    /// A counter could go past the `i8` range of `Tile::Neighbor`
+    CounterOverflow {
+        neighbors: u32,
+        max_mines: u8,
+    },
+    /// `Neighborhood::Custom` without any offset besides the tile itself
+    EmptyNeighborhood,
+    /// `Neighborhood::Custom` offset pointing at the tile itself or repeated
+    InvalidOffset((i8, i8)),
+    /// A toroidal board narrower than the neighborhood, wrapping it onto itself
+    TorusTooSmall {
+        size: (u16, u16),
+        min: (u16, u16),
+    },
+    /// `TileSize::Adaptive` whose `min` is above its `max`
+    InvalidAdaptiveSize {
+        min: f32,
+        max: f32,
+    },
+}
+
+impl Display for BoardOptionsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::ZeroSize => write!(f, "the board has no tile"),
+            Self::TooLarge { tiles } => {
+                write!(
+                    f,
+                    "the board has {tiles} tiles, at most {} are supported",
+                    u16::MAX
+                )
+            }
+            Self::TooManyBombs { bombs, max } => {
+                write!(f, "{bombs} mines don't fit on the board, at most {max} do")
+            }
+            Self::TooManyAntiBombs { anti_bombs, max } => {
+                write!(
+                    f,
+                    "{anti_bombs} anti-mines don't fit next to the mines, at most {max} do"
+                )
+            }
+            Self::NegativePadding(padding) => write!(f, "negative tile padding {padding}"),
+            Self::PaddingTooLarge { padding, tile_size } => {
+                write!(
+                    f,
+                    "tile padding {padding} isn't smaller than the tile size {tile_size}"
+                )
+            }
+            Self::NoGuessWithoutSafeStart => {
+                write!(f, "no guess generation needs a safe start")
+            }
+            Self::RadiusTooLarge(radius) => {
+                write!(
+                    f,
+                    "neighborhood radius {radius} is larger than {}",
+                    Neighborhood::MAX_RADIUS
+                )
+            }
+            Self::CounterOverflow {
+                neighbors,
+                max_mines,
+            } => {
+                write!(
+                    f,
+                    "{neighbors} neighbors with {max_mines} mines each exceed the largest counter {}",
+                    i8::MAX
+                )
+            }
+            Self::EmptyNeighborhood => write!(f, "the custom neighborhood has no offset"),
+            Self::InvalidOffset((x, y)) => {
+                write!(
+                    f,
+                    "custom neighborhood offset ({x}, {y}) is the tile itself or repeated"
+                )
+            }
+            Self::TorusTooSmall {
+                size: (width, height),
+                min: (min_width, min_height),
+            } => {
+                write!(
+                    f,
+                    "the {width}x{height} torus is smaller than its {min_width}x{min_height} neighborhood"
+                )
+            }
+            Self::InvalidAdaptiveSize { min, max } => {
+                write!(
+                    f,
+                    "adaptive tile size minimum {min} is above its maximum {max}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for BoardOptionsError {}
+
+impl From<PlacementError> for BoardOptionsError {
+    fn from(e: PlacementError) -> Self {
+        match e {
+            PlacementError::NotEnoughTiles { mines, available } => Self::TooManyBombs {
+                bombs: mines as u32,
+                max: available as u32,
+            },
+        }
+    }
+}
+
+/// Failure to create a board, reported by `BoardErrorEvent`
+#[derive(Debug)]
+pub enum BoardError {
+    Options(BoardOptionsError),
+    /// The file of a `MaskSource::File` couldn't be read
+    Mask(MaskError),
+}
+
+impl Display for BoardError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Options(e) => write!(f, "{e}"),
+            Self::Mask(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+impl From<BoardOptionsError> for BoardError {
+    fn from(e: BoardOptionsError) -> Self {
+        Self::Options(e)
+    }
+}
+
+impl From<MaskError> for BoardError {
+    fn from(e: MaskError) -> Self {
+        Self::Mask(e)
+    }
+}
+
+impl From<PlacementError> for BoardError {
+    fn from(e: PlacementError) -> Self {
+        Self::Options(e.into())
+    }
+}
+
+impl BoardOptions {
+    /// Shape mask of the board, `None` for a mask file that wasn't read yet
+    pub fn shape_mask(&self) -> Option<&ShapeMask> {
+        self.mask.as_ref().and_then(MaskSource::shape)
+    }
+
+    /// Checks the board can be generated and displayed with these options
+    ///
+    /// At least one tile outside of the safe start area must stay clear of mines
+    pub fn validate(&self) -> Result<(), BoardOptionsError> {
+        let (width, height) = self.shape_mask().map_or(self.map_size, ShapeMask::size);
+        let tiles = width as u32 * height as u32;
+        if tiles == 0 {
+            return Err(BoardOptionsError::ZeroSize);
+        }
+        if tiles > u16::MAX as u32 {
+            return Err(BoardOptionsError::TooLarge { tiles });
+        }
+
+        match &self.neighborhood {
+            Neighborhood::Radius(radius) if *radius > Neighborhood::MAX_RADIUS => {
+                return Err(BoardOptionsError::RadiusTooLarge(*radius));
+            }
+            Neighborhood::Custom(offsets) => {
+                let mut seen = HashSet::new();
+                if let Some(offset) = offsets
+                    .iter()
+                    .find(|offset| **offset == (0, 0) || !seen.insert(**offset))
+                {
+                    return Err(BoardOptionsError::InvalidOffset(*offset));
+                }
+                if offsets.is_empty() {
+                    return Err(BoardOptionsError::EmptyNeighborhood);
+                }
+            }
+            _ => (),
+        }
+        if self.topology.wraps() {
+            let (reach_x, reach_y) = self
+                .neighborhood
+                .offsets()
+                .fold((0, 0), |(x, y), (dx, dy)| {
+                    (x.max(dx.unsigned_abs()), y.max(dy.unsigned_abs()))
+                });
+            let min = (2 * reach_x as u16 + 1, 2 * reach_y as u16 + 1);
+            if width < min.0 || height < min.1 {
+                return Err(BoardOptionsError::TorusTooSmall {
+                    size: (width, height),
+                    min,
+                });
+            }
+        }
+
+        let playable = self.shape_mask().map_or(tiles, ShapeMask::tile_count);
+        let safe_tiles = self
+            .safe_start
+            .safe_tiles(&self.topology, &self.neighborhood);
+        let usable = playable.saturating_sub(safe_tiles).saturating_sub(1);
+        let per_tile = self.max_mines_per_tile.max(1) as u32;
+        let bombs = self.bomb_count as u32;
+        let max = usable * per_tile;
+        if bombs > max {
+            return Err(BoardOptionsError::TooManyBombs { bombs, max });
+        }
+        // negative mines only go on tiles without regular ones, which may
+        // each take a tile of their own
+        let anti_bombs = self.anti_bomb_count as u32;
+        let max = (usable - bombs.min(usable)) * per_tile;
+        if anti_bombs > max {
+            return Err(BoardOptionsError::TooManyAntiBombs { anti_bombs, max });
+        }
+
+        if self.tile_padding < 0. {
+            return Err(BoardOptionsError::NegativePadding(self.tile_padding));
+        }
+        let tile_size = match self.tile_size {
+            TileSize::Fixed(size) => size,
+            TileSize::Adaptive { min, max } if min > max => {
+                return Err(BoardOptionsError::InvalidAdaptiveSize { min, max });
+            }
+            TileSize::Adaptive { min, .. } => min,
+        };
+        if self.tile_padding >= tile_size {
+            return Err(BoardOptionsError::PaddingTooLarge {
+                padding: self.tile_padding,
+                tile_size,
+            });
+        }
+
+        let neighbors = self.neighbor_count();
+        if neighbors * self.max_mines_per_tile as u32 > i8::MAX as u32 {
+            return Err(BoardOptionsError::CounterOverflow {
+                neighbors,
+                max_mines: self.max_mines_per_tile,
+            });
+        }
+        if matches!(self.generation, Generation::NoGuess { .. })
+            && matches!(self.safe_start, SafeStart::Disabled)
+        {
+            return Err(BoardOptionsError::NoGuessWithoutSafeStart);
+        }
+        Ok(())
+    }
+
+    /// Number of neighbors of a tile, following the topology and neighborhood
+    fn neighbor_count(&self) -> u32 {
+        let count = match self.topology.neighbor_offsets(Default::default()) {
+            Some(offsets) => offsets.len(),
+            None => self.neighborhood.offsets().count(),
+        };
+        count as u32
+    }
 }
 
 impl Default for BoardOptions {
@@ -68,7 +451,139 @@ impl Default for BoardOptions {
             position: Default::default(),
             tile_size: Default::default(),
             tile_padding: 0.,
-            safe_start: false,
+            safe_start: Default::default(),
+            seed: None,
+            generation: Default::default(),
+            chord_bindings: Default::default(),
+            hud: false,
+            history: false,
+            question_marks: false,
+            topology: Default::default(),
+            neighborhood: Default::default(),
+            mask: None,
+            max_mines_per_tile: 1,
+            anti_bomb_count: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::{HexCoordinates, HexOrientation};
+
+    fn first_click(radius: u8) -> SafeStart {
+        SafeStart::FirstClick { radius }
+    }
+
+    #[test]
+    fn safe_tiles_follow_the_neighborhood() {
+        let square = Topology::Square;
+        assert_eq!(first_click(1).safe_tiles(&square, &Neighborhood::Moore), 9);
+        assert_eq!(first_click(2).safe_tiles(&square, &Neighborhood::Moore), 25);
+        assert_eq!(
+            first_click(2).safe_tiles(&square, &Neighborhood::VonNeumann),
+            13
+        );
+        assert_eq!(first_click(1).safe_tiles(&square, &Neighborhood::Knight), 9);
+        assert_eq!(
+            first_click(1).safe_tiles(&square, &Neighborhood::Radius(2)),
+            25
+        );
+        assert_eq!(
+            SafeStart::FirstEmpty.safe_tiles(&square, &Neighborhood::Moore),
+            0
+        );
+
+        let hexagonal = Topology::Hexagonal {
+            orientation: HexOrientation::Pointy,
+            coordinates: HexCoordinates::Offset,
+        };
+        assert_eq!(
+            first_click(1).safe_tiles(&hexagonal, &Neighborhood::Moore),
+            7
+        );
+        assert_eq!(
+            first_click(2).safe_tiles(&hexagonal, &Neighborhood::Moore),
+            19
+        );
+    }
+
+    #[test]
+    fn invalid_options() {
+        let invalid = |options: BoardOptions| options.validate().unwrap_err();
+
+        assert!(matches!(
+            invalid(BoardOptions {
+                neighborhood: Neighborhood::Custom(vec![]),
+                ..Default::default()
+            }),
+            BoardOptionsError::EmptyNeighborhood
+        ));
+        assert!(matches!(
+            invalid(BoardOptions {
+                neighborhood: Neighborhood::Custom(vec![(1, 0), (0, 0)]),
+                ..Default::default()
+            }),
+            BoardOptionsError::InvalidOffset((0, 0))
+        ));
+        assert!(matches!(
+            invalid(BoardOptions {
+                neighborhood: Neighborhood::Custom(vec![(1, 0), (1, 0)]),
+                ..Default::default()
+            }),
+            BoardOptionsError::InvalidOffset((1, 0))
+        ));
+        assert!(matches!(
+            invalid(BoardOptions {
+                map_size: (4, 9),
+                bomb_count: 1,
+                topology: Topology::Toroidal { ghost_edges: false },
+                neighborhood: Neighborhood::Radius(2),
+                ..Default::default()
+            }),
+            BoardOptionsError::TorusTooSmall {
+                size: (4, 9),
+                min: (5, 5)
+            }
+        ));
+        // the mines may take a tile each, leaving none for the anti-mines
+        assert_eq!(
+            invalid(BoardOptions {
+                map_size: (3, 3),
+                bomb_count: 8,
+                max_mines_per_tile: 2,
+                anti_bomb_count: 1,
+                ..Default::default()
+            }),
+            BoardOptionsError::TooManyAntiBombs {
+                anti_bombs: 1,
+                max: 0
+            }
+        );
+        assert!(BoardOptions {
+            map_size: (3, 3),
+            bomb_count: 4,
+            max_mines_per_tile: 2,
+            anti_bomb_count: 8,
+            ..Default::default()
         }
+        .validate()
+        .is_ok());
+        assert!(matches!(
+            invalid(BoardOptions {
+                tile_size: TileSize::Adaptive { min: 20., max: 10. },
+                ..Default::default()
+            }),
+            BoardOptionsError::InvalidAdaptiveSize { .. }
+        ));
+        assert!(matches!(
+            invalid(BoardOptions {
+                neighborhood: Neighborhood::Radius(Neighborhood::MAX_RADIUS + 1),
+                safe_start: first_click(u8::MAX),
+                ..Default::default()
+            }),
+            BoardOptionsError::RadiusTooLarge(_)
+        ));
     }
 }
@@ -0,0 +1,50 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Failure to read or write a RON file of the data directory
+#[derive(Debug)]
+pub enum DataFileError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+}
+
+impl Display for DataFileError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "file access failed: {e}"),
+            Self::Serialize(e) => write!(f, "serialization failed: {e}"),
+            Self::Deserialize(e) => write!(f, "invalid file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DataFileError {}
+
+/// File `name` of the game in the platform data directory, if there is one
+pub(crate) fn data_path(name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("bevy-minesweeper").join(name))
+}
+
+/// Reads the value stored at `path`, `None` if the file doesn't exist yet
+pub(crate) fn read<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, DataFileError> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => ron::from_str(&content)
+            .map(Some)
+            .map_err(DataFileError::Deserialize),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(DataFileError::Io(e)),
+    }
+}
+
+/// Writes `value` to `path`, creating its directory if needed
+pub(crate) fn write<T: Serialize>(path: &Path, value: &T) -> Result<(), DataFileError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(DataFileError::Io)?;
+    }
+    let content =
+        ron::ser::to_string_pretty(value, Default::default()).map_err(DataFileError::Serialize)?;
+    std::fs::write(path, content).map_err(DataFileError::Io)
+}
@@ -0,0 +1,105 @@
+use crate::resources::data_file::{self, DataFileError};
+use crate::resources::BoardOptions;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+
+/// Board size and bomb count of a new game
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Difficulty {
+    /// 9x9 board with 10 bombs
+    #[default]
+    Beginner,
+    /// 16x16 board with 40 bombs
+    Intermediate,
+    /// 30x16 board with 99 bombs
+    Expert,
+    Custom {
+        map_size: (u16, u16),
+        bomb_count: u16,
+    },
+}
+
+impl Difficulty {
+    pub const PRESETS: [Self; 3] = [Self::Beginner, Self::Intermediate, Self::Expert];
+
+    pub fn map_size(&self) -> (u16, u16) {
+        match self {
+            Self::Beginner => (9, 9),
+            Self::Intermediate => (16, 16),
+            Self::Expert => (30, 16),
+            Self::Custom { map_size, .. } => *map_size,
+        }
+    }
+
+    pub fn bomb_count(&self) -> u16 {
+        match self {
+            Self::Beginner => 10,
+            Self::Intermediate => 40,
+            Self::Expert => 99,
+            Self::Custom { bomb_count, .. } => *bomb_count,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Beginner => "Beginner",
+            Self::Intermediate => "Intermediate",
+            Self::Expert => "Expert",
+            Self::Custom { .. } => "Custom",
+        }
+    }
+
+    /// Sets the board size and bomb count of `options`
+    pub fn apply(&self, options: &mut BoardOptions) {
+        options.map_size = self.map_size();
+        options.bomb_count = self.bomb_count();
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (width, height) = self.map_size();
+        write!(
+            f,
+            "{} {width}x{height}, {} mines",
+            self.name(),
+            self.bomb_count()
+        )
+    }
+}
+
+/// Difficulty of the last `NewGameEvent`, `None` until one is sent
+///
+/// Always present, unlike `DifficultySettings`, so that option files loaded
+/// after the pick keep it
+#[derive(Debug, Default, Copy, Clone, Resource)]
+pub struct ChosenDifficulty(pub Option<Difficulty>);
+
+/// Last difficulty picked in the new game menu, saved to `path`
+#[derive(Debug, Clone, Resource)]
+pub struct DifficultySettings {
+    pub path: PathBuf,
+    pub difficulty: Difficulty,
+}
+
+impl DifficultySettings {
+    /// Settings file in the platform data directory, if there is one
+    pub fn default_path() -> Option<PathBuf> {
+        data_file::data_path("difficulty.ron")
+    }
+
+    /// Reads the settings stored at `path`, defaulting to `Difficulty::Beginner`
+    /// if the file doesn't exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, DataFileError> {
+        let path = path.into();
+        let difficulty = data_file::read(&path)?.unwrap_or_default();
+        Ok(Self { path, difficulty })
+    }
+
+    pub fn save(&self) -> Result<(), DataFileError> {
+        data_file::write(&self.path, &self.difficulty)
+    }
+}
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+/// Time spent playing the current board
+///
+/// Starts on the first uncovered tile, only ticks while the host app is in the
+/// running state and stops for good once the game is over
+#[derive(Debug, Default, Clone, Resource)]
+pub struct GameClock {
+    elapsed: Duration,
+    started: bool,
+    stopped: bool,
+}
+
+impl GameClock {
+    /// Clock of a resumed game, already started unless `elapsed` is zero
+    pub fn resumed(elapsed: Duration) -> Self {
+        Self {
+            elapsed,
+            started: !elapsed.is_zero(),
+            stopped: false,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started && !self.stopped
+    }
+
+    /// Starts the clock, does nothing while stopped
+    pub fn start(&mut self) {
+        self.started = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    /// Lets a stopped clock run again, used when undoing the end of a game
+    pub fn resume(&mut self) {
+        self.stopped = false;
+    }
+
+    /// Advances the clock by `delta` if running
+    pub fn tick(&mut self, delta: Duration) {
+        if self.is_running() {
+            self.elapsed += delta;
+        }
+    }
+}
+
+/// Bombs left to flag, negative when more tiles are flagged than there are bombs
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Resource)]
+pub struct MineCounter(pub i32);
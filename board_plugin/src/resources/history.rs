@@ -0,0 +1,142 @@
+use crate::resources::ClickCounts;
+use crate::{Coordinates, TileMark};
+
+/// Reversible changes made to the board by a single player action
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct BoardAction {
+    /// Tiles uncovered together: flood fill, chord or bombs revealed on loss
+    pub uncovered: Vec<Coordinates>,
+    /// Tiles whose mark changed
+    pub marks: Vec<MarkChange>,
+    /// Click counted for the action, removed again on undo
+    pub clicks: ClickCounts,
+}
+
+/// Mark of a tile before and after an action
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MarkChange {
+    pub coords: Coordinates,
+    pub before: Option<TileMark>,
+    pub after: Option<TileMark>,
+}
+
+impl BoardAction {
+    pub fn is_empty(&self) -> bool {
+        self.uncovered.is_empty() && self.marks.is_empty()
+    }
+}
+
+/// Undo and redo stacks of board actions
+#[derive(Debug, Default, Clone)]
+pub struct History {
+    undo: Vec<BoardAction>,
+    redo: Vec<BoardAction>,
+}
+
+impl History {
+    /// Records a new action, dropping the actions left to redo
+    pub fn record(&mut self, action: BoardAction) {
+        if action.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push(action);
+    }
+
+    /// Merges `action` into the last recorded action, so consequences of a
+    /// move are undone with it
+    pub fn extend_last(&mut self, action: BoardAction) {
+        match self.undo.last_mut() {
+            Some(last) => {
+                last.uncovered.extend(action.uncovered);
+                last.marks.extend(action.marks);
+            }
+            None => self.record(action),
+        }
+    }
+
+    /// Pops the last action to revert
+    pub fn undo(&mut self) -> Option<BoardAction> {
+        let action = self.undo.pop()?;
+        self.redo.push(action.clone());
+        Some(action)
+    }
+
+    /// Pops the last reverted action to apply again
+    pub fn redo(&mut self) -> Option<BoardAction> {
+        let action = self.redo.pop()?;
+        self.undo.push(action.clone());
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uncover(x: u16) -> BoardAction {
+        BoardAction {
+            uncovered: vec![Coordinates { x, y: 0 }],
+            clicks: ClickCounts {
+                left: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::default();
+        history.record(uncover(0));
+        history.record(uncover(1));
+
+        assert_eq!(history.undo(), Some(uncover(1)));
+        assert_eq!(history.undo(), Some(uncover(0)));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(uncover(0)));
+        assert_eq!(history.redo(), Some(uncover(1)));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(uncover(1)));
+    }
+
+    #[test]
+    fn record_clears_the_redo_stack() {
+        let mut history = History::default();
+        history.record(uncover(0));
+        history.undo();
+        history.record(uncover(1));
+
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(uncover(1)));
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn empty_actions_are_ignored() {
+        let mut history = History::default();
+        history.record(uncover(0));
+        history.undo();
+        history.record(BoardAction::default());
+
+        assert_eq!(history.redo(), Some(uncover(0)));
+    }
+
+    #[test]
+    fn extend_last_merges_the_action() {
+        let mut history = History::default();
+        history.extend_last(uncover(0));
+        history.extend_last(BoardAction {
+            uncovered: vec![Coordinates { x: 2, y: 0 }],
+            ..Default::default()
+        });
+
+        let action = history.undo().unwrap();
+        assert_eq!(
+            action.uncovered,
+            [Coordinates { x: 0, y: 0 }, Coordinates { x: 2, y: 0 }]
+        );
+        assert_eq!(action.clicks.left, 1);
+        assert_eq!(history.undo(), None);
+    }
+}
@@ -0,0 +1,329 @@
+use crate::resources::data_file::{self, DataFileError};
+use crate::resources::{
+    BoardOptions, Generation, MaskSource, Neighborhood, SafeStart, ShapeMask, Topology,
+};
+
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+
+/// Entries kept per leaderboard table
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// Board settings whose games are ranked together
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct LeaderboardKey {
+    pub map_size: (u16, u16),
+    pub bomb_count: u16,
+    /// Rules differing from the classic game, `Classic` if none
+    pub variant: String,
+}
+
+impl LeaderboardKey {
+    pub fn new(options: &BoardOptions) -> Self {
+        let mut variant = Vec::new();
+        match options.topology {
+            Topology::Square => (),
+            Topology::Hexagonal {
+                orientation,
+                coordinates,
+            } => variant.push(format!(
+                "{orientation:?} hexagonal, {coordinates:?} coordinates"
+            )),
+            Topology::Toroidal { .. } => variant.push("toroidal".to_string()),
+        }
+        match &options.neighborhood {
+            // hexagonal tiles ignore the neighborhood
+            _ if matches!(options.topology, Topology::Hexagonal { .. }) => (),
+            Neighborhood::Moore => (),
+            Neighborhood::Custom(offsets) => {
+                let mut offsets = offsets.clone();
+                offsets.sort_unstable();
+                variant.push(format!("custom neighborhood {offsets:?}"));
+            }
+            neighborhood => variant.push(format!("{neighborhood:?} neighborhood")),
+        }
+        match &options.mask {
+            None => (),
+            Some(MaskSource::Inline(mask)) => {
+                variant.push(format!("mask {:016x}", mask.fingerprint()));
+            }
+            Some(MaskSource::File(path)) => variant.push(format!("mask {}", path.display())),
+        }
+        match options.safe_start {
+            SafeStart::Disabled => (),
+            SafeStart::FirstEmpty => variant.push("first empty start".to_string()),
+            SafeStart::FirstClick { radius } => {
+                variant.push(format!("safe first click radius {radius}"));
+            }
+        }
+        if options.max_mines_per_tile > 1 {
+            variant.push(format!("{} mines per tile", options.max_mines_per_tile));
+        }
+        if options.anti_bomb_count > 0 {
+            variant.push(format!("{} anti-mines", options.anti_bomb_count));
+        }
+        match &options.generation {
+            Generation::Random => (),
+            Generation::NoGuess { .. } => variant.push("no guess".to_string()),
+            Generation::FromFile(path) => variant.push(format!("layout {}", path.display())),
+        }
+        if options.history {
+            variant.push("undo".to_string());
+        }
+        let (width, height) = options
+            .shape_mask()
+            .map_or(options.map_size, ShapeMask::size);
+        Self {
+            map_size: (width, height),
+            bomb_count: options.bomb_count,
+            variant: match variant.is_empty() {
+                true => "Classic".to_string(),
+                false => variant.join(", "),
+            },
+        }
+    }
+}
+
+impl Display for LeaderboardKey {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (width, height) = self.map_size;
+        write!(
+            f,
+            "{width}x{height}, {} mines, {}",
+            self.bomb_count, self.variant
+        )
+    }
+}
+
+/// Won game of a leaderboard table
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LeaderboardEntry {
+    pub time: Duration,
+    pub three_bv_per_second: f32,
+    pub seed: u64,
+    pub date: DateTime<Utc>,
+}
+
+/// Best games of a board setting, fastest first
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LeaderboardTable {
+    pub key: LeaderboardKey,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+/// Local high score store, saved to `path` whenever a game enters it
+///
+#[derive(Debug, Clone, Resource)]
+pub struct Leaderboard {
+    pub path: PathBuf,
+    pub tables: Vec<LeaderboardTable>,
+}
+
+impl Leaderboard {
+    /// Leaderboard file in the platform data directory, if there is one
+    pub fn default_path() -> Option<PathBuf> {
+        data_file::data_path("leaderboard.ron")
+    }
+
+    /// Reads the leaderboard stored at `path`, starting an empty one if the
+    /// file doesn't exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, DataFileError> {
+        let path = path.into();
+        let tables = data_file::read(&path)?.unwrap_or_default();
+        Ok(Self { path, tables })
+    }
+
+    pub fn save(&self) -> Result<(), DataFileError> {
+        data_file::write(&self.path, &self.tables)
+    }
+
+    /// Best games of the `key` board setting, fastest first
+    pub fn entries(&self, key: &LeaderboardKey) -> &[LeaderboardEntry] {
+        self.tables
+            .iter()
+            .find(|table| table.key == *key)
+            .map_or(&[], |table| &table.entries)
+    }
+
+    /// Ranks `entry` among the games of `key`, returning its rank from 0 if it
+    /// is one of the `LEADERBOARD_SIZE` best
+    pub fn record(&mut self, key: LeaderboardKey, entry: LeaderboardEntry) -> Option<usize> {
+        let index = match self.tables.iter().position(|table| table.key == key) {
+            Some(index) => index,
+            None => {
+                self.tables.push(LeaderboardTable {
+                    key,
+                    entries: Vec::new(),
+                });
+                self.tables.len() - 1
+            }
+        };
+        let entries = &mut self.tables[index].entries;
+        let rank = entries.partition_point(|e| e.time <= entry.time);
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+        entries.insert(rank, entry);
+        entries.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::HexOrientation;
+
+    fn entry(secs: u64) -> LeaderboardEntry {
+        LeaderboardEntry {
+            time: Duration::from_secs(secs),
+            three_bv_per_second: 1.,
+            seed: secs,
+            date: DateTime::UNIX_EPOCH,
+        }
+    }
+
+    fn leaderboard(path: PathBuf) -> Leaderboard {
+        Leaderboard {
+            path,
+            tables: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn record_ranks_fastest_first() {
+        let mut leaderboard = leaderboard(PathBuf::new());
+        let key = LeaderboardKey::new(&BoardOptions::default());
+
+        assert_eq!(leaderboard.record(key.clone(), entry(20)), Some(0));
+        assert_eq!(leaderboard.record(key.clone(), entry(10)), Some(0));
+        assert_eq!(leaderboard.record(key.clone(), entry(30)), Some(2));
+        // ties rank after the games already there
+        assert_eq!(leaderboard.record(key.clone(), entry(20)), Some(2));
+
+        let times: Vec<_> = leaderboard
+            .entries(&key)
+            .iter()
+            .map(|e| e.time.as_secs())
+            .collect();
+        assert_eq!(times, [10, 20, 20, 30]);
+    }
+
+    #[test]
+    fn history_games_are_ranked_apart() {
+        let mut leaderboard = leaderboard(PathBuf::new());
+        let classic = LeaderboardKey::new(&BoardOptions::default());
+        let undo = LeaderboardKey::new(&BoardOptions {
+            history: true,
+            ..Default::default()
+        });
+        assert_ne!(classic, undo);
+
+        leaderboard.record(undo.clone(), entry(10));
+        assert!(leaderboard.entries(&classic).is_empty());
+        assert_eq!(leaderboard.entries(&undo).len(), 1);
+    }
+
+    #[test]
+    fn keys_tell_variants_apart() {
+        let key = |options: BoardOptions| LeaderboardKey::new(&options);
+        let mask = |rows: &str| Some(MaskSource::Inline(ShapeMask::from_ascii(rows).unwrap()));
+        let custom = |offsets: Vec<(i8, i8)>| BoardOptions {
+            neighborhood: Neighborhood::Custom(offsets),
+            ..Default::default()
+        };
+        let hexagonal = |orientation| BoardOptions {
+            topology: Topology::Hexagonal {
+                orientation,
+                coordinates: Default::default(),
+            },
+            ..Default::default()
+        };
+
+        assert_ne!(
+            key(custom(vec![(1, 0), (0, 1)])),
+            key(custom(vec![(1, 0), (0, 2)]))
+        );
+        assert_eq!(
+            key(custom(vec![(1, 0), (0, 1)])),
+            key(custom(vec![(0, 1), (1, 0)]))
+        );
+        assert_ne!(
+            key(BoardOptions {
+                mask: mask("##\n#."),
+                ..Default::default()
+            }),
+            key(BoardOptions {
+                mask: mask("##\n.#"),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            key(BoardOptions {
+                mask: mask("##\n#."),
+                ..Default::default()
+            }),
+            key(BoardOptions {
+                mask: mask("##\n#.\n.."),
+                ..Default::default()
+            })
+        );
+        assert_ne!(
+            key(hexagonal(HexOrientation::Pointy)),
+            key(hexagonal(HexOrientation::Flat))
+        );
+        assert_ne!(
+            key(BoardOptions {
+                safe_start: SafeStart::FirstClick { radius: 1 },
+                ..Default::default()
+            }),
+            key(BoardOptions {
+                safe_start: SafeStart::FirstClick { radius: 2 },
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn record_keeps_the_best_games() {
+        let mut leaderboard = leaderboard(PathBuf::new());
+        let key = LeaderboardKey::new(&BoardOptions::default());
+        for secs in 1..=LEADERBOARD_SIZE as u64 {
+            leaderboard.record(key.clone(), entry(secs * 10));
+        }
+
+        let slowest = LEADERBOARD_SIZE as u64 * 10;
+        assert_eq!(leaderboard.record(key.clone(), entry(slowest + 1)), None);
+        assert_eq!(leaderboard.record(key.clone(), entry(5)), Some(0));
+
+        let entries = leaderboard.entries(&key);
+        assert_eq!(entries.len(), LEADERBOARD_SIZE);
+        assert_eq!(entries[0].time.as_secs(), 5);
+        assert_eq!(entries[LEADERBOARD_SIZE - 1].time.as_secs(), slowest - 10);
+    }
+
+    #[test]
+    fn save_and_open() {
+        let dir = std::env::temp_dir().join(format!("leaderboard-test-{}", std::process::id()));
+        let path = dir.join("leaderboard.ron");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(Leaderboard::open(&path).unwrap().tables.is_empty());
+
+        let mut saved = leaderboard(path.clone());
+        let key = LeaderboardKey::new(&BoardOptions::default());
+        saved.record(key.clone(), entry(10));
+        saved.record(key.clone(), entry(20));
+        saved.save().unwrap();
+
+        let opened = Leaderboard::open(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(opened.tables.len(), 1);
+        assert_eq!(opened.tables[0].key, key);
+        assert_eq!(opened.entries(&key), saved.entries(&key));
+    }
+}
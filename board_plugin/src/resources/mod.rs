@@ -1,8 +1,37 @@
 pub(crate) mod board;
 mod board_assets;
+mod board_files;
+mod board_layout;
 mod board_options;
+mod data_file;
+mod difficulty;
+mod game_clock;
+mod history;
+mod leaderboard;
+mod neighborhood;
+mod player_stats;
+mod replay;
+mod saved_game;
+mod shape_mask;
 pub(crate) mod tile;
 pub(crate) mod tile_map;
+mod topology;
 
+pub use board::{Board, ClickCounts};
 pub use board_assets::*;
+pub use board_files::*;
+pub use board_layout::*;
 pub use board_options::*;
+pub use data_file::DataFileError;
+pub use difficulty::*;
+pub use game_clock::*;
+pub use history::*;
+pub use leaderboard::*;
+pub use neighborhood::*;
+pub use player_stats::*;
+pub use replay::*;
+pub use saved_game::*;
+pub use shape_mask::*;
+pub use tile::Tile;
+pub use tile_map::{PlacementError, TileMap, PLACEMENT_VERSION};
+pub use topology::*;
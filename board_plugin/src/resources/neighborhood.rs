@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Delta coordinates for all 8 adjacent tiles
+///
+/// *--------*-------*-------*
+/// | -1, 1  | 0, 1  | 1, 1  |
+/// |--------|-------|-------|
+/// | -1, 0  | tile  | 1, 0  |
+/// |--------|-------|-------|
+/// | -1, -1 | 0, -1 | 1, -1 |
+/// *--------*-------*-------*
+///
+const MOORE_OFFSETS: [(i8, i8); 8] = [
+    // bottom left
+    (-1, -1),
+    // bottom
+    (0, -1),
+    // bottom right
+    (1, -1),
+    // left
+    (-1, 0),
+    // right
+    (1, 0),
+    // top left
+    (-1, 1),
+    // top
+    (0, 1),
+    // top right
+    (1, 1),
+];
+
+/// Delta coordinates for the 4 orthogonally adjacent tiles
+const VON_NEUMANN_OFFSETS: [(i8, i8); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// Delta coordinates for the 8 tiles a chess knight moves to
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (-1, -2),
+    (1, -2),
+    (-2, -1),
+    (2, -1),
+    (-2, 1),
+    (2, 1),
+    (-1, 2),
+    (1, 2),
+];
+
+/// Tiles counted as neighbors of a square tile, used for bomb counts,
+/// flood fill and chording
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Neighborhood {
+    /// The 8 adjacent tiles
+    #[default]
+    Moore,
+    /// The 4 orthogonally adjacent tiles
+    VonNeumann,
+    /// The 8 tiles a chess knight moves to
+    Knight,
+    /// Every tile within the given distance on both axes, up to `MAX_RADIUS`
+    Radius(u8),
+    /// Arbitrary delta coordinates
+    Custom(Vec<(i8, i8)>),
+}
+
+impl Neighborhood {
+    /// Largest `Radius`, whose 120 neighbors keep the counters within an `i8`
+    pub const MAX_RADIUS: u8 = 5;
+
+    /// Delta coordinates of the neighbors of a tile, each counted once and
+    /// never the tile itself
+    pub fn offsets(&self) -> impl Iterator<Item = (i8, i8)> + '_ {
+        let (offsets, radius): (&[(i8, i8)], i8) = match self {
+            Self::Moore => (&MOORE_OFFSETS, 0),
+            Self::VonNeumann => (&VON_NEUMANN_OFFSETS, 0),
+            Self::Knight => (&KNIGHT_OFFSETS, 0),
+            Self::Radius(radius) => (&[], (*radius).min(i8::MAX as u8) as i8),
+            Self::Custom(offsets) => (offsets, 0),
+        };
+        let square =
+            (-radius..=radius).flat_map(move |dy| (-radius..=radius).map(move |dx| (dx, dy)));
+        let mut seen = HashSet::new();
+        offsets
+            .iter()
+            .copied()
+            .chain(square)
+            .filter(move |offset| *offset != (0, 0) && seen.insert(*offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_offsets_are_unique() {
+        let neighborhood = Neighborhood::Custom(vec![(0, 0), (1, 0), (0, 1), (1, 0)]);
+        let offsets: Vec<_> = neighborhood.offsets().collect();
+
+        assert_eq!(offsets, [(1, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn radius_offsets() {
+        assert_eq!(Neighborhood::Radius(1).offsets().count(), 8);
+        assert_eq!(
+            Neighborhood::Radius(Neighborhood::MAX_RADIUS)
+                .offsets()
+                .count(),
+            120
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use crate::resources::data_file::{self, DataFileError};
+use crate::resources::LeaderboardKey;
+
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Aggregated results of the finished games of a board setting
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GameStats {
+    pub played: u32,
+    pub won: u32,
+    pub lost: u32,
+    /// Games won in a row, reset by a loss
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// Total time of the won games
+    pub total_time: Duration,
+    pub tiles_revealed: u64,
+    pub flags_placed: u64,
+    /// Flags matching the count and sign of the mines of their tile
+    pub correct_flags: u64,
+}
+
+impl GameStats {
+    pub fn record_win(&mut self, time: Duration) {
+        self.played += 1;
+        self.won += 1;
+        self.current_streak += 1;
+        self.best_streak = self.best_streak.max(self.current_streak);
+        self.total_time += time;
+    }
+
+    pub fn record_loss(&mut self) {
+        self.played += 1;
+        self.lost += 1;
+        self.current_streak = 0;
+    }
+
+    /// Average time of the won games
+    pub fn average_time(&self) -> Option<Duration> {
+        (self.won > 0).then(|| self.total_time / self.won)
+    }
+
+    /// Share of the played games won, from 0 to 1
+    pub fn win_rate(&self) -> Option<f32> {
+        (self.played > 0).then(|| self.won as f32 / self.played as f32)
+    }
+
+    /// Share of the placed flags put on mines, from 0 to 1
+    pub fn accuracy(&self) -> Option<f32> {
+        (self.flags_placed > 0).then(|| self.correct_flags as f32 / self.flags_placed as f32)
+    }
+
+    /// Adds the results of `other`, streaks keep their best value
+    fn merge(&mut self, other: &Self) {
+        self.played += other.played;
+        self.won += other.won;
+        self.lost += other.lost;
+        self.current_streak = self.current_streak.max(other.current_streak);
+        self.best_streak = self.best_streak.max(other.best_streak);
+        self.total_time += other.total_time;
+        self.tiles_revealed += other.tiles_revealed;
+        self.flags_placed += other.flags_placed;
+        self.correct_flags += other.correct_flags;
+    }
+}
+
+/// Stats of a board setting
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DifficultyStats {
+    pub key: LeaderboardKey,
+    pub stats: GameStats,
+}
+
+/// Failure to export the player stats
+#[derive(Debug)]
+pub enum StatsError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl Display for StatsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "stats export file access failed: {e}"),
+            Self::Json(e) => write!(f, "failed to export stats: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StatsError {}
+
+/// Lifetime stats of the player, saved to `path` after every finished game
+#[derive(Debug, Clone, Resource)]
+pub struct PlayerStats {
+    pub path: PathBuf,
+    pub difficulties: Vec<DifficultyStats>,
+}
+
+impl PlayerStats {
+    /// Stats file in the platform data directory, if there is one
+    pub fn default_path() -> Option<PathBuf> {
+        data_file::data_path("stats.ron")
+    }
+
+    /// Reads the stats stored at `path`, starting from scratch if the file
+    /// doesn't exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, DataFileError> {
+        let path = path.into();
+        let difficulties = data_file::read(&path)?.unwrap_or_default();
+        Ok(Self { path, difficulties })
+    }
+
+    pub fn save(&self) -> Result<(), DataFileError> {
+        data_file::write(&self.path, &self.difficulties)
+    }
+
+    /// Writes the stats of every board setting to `path` as JSON
+    pub fn export_json(&self, path: &Path) -> Result<(), StatsError> {
+        let content = serde_json::to_string_pretty(&self.difficulties).map_err(StatsError::Json)?;
+        std::fs::write(path, content).map_err(StatsError::Io)
+    }
+
+    /// Stats of the `key` board setting
+    pub fn stats(&self, key: &LeaderboardKey) -> GameStats {
+        self.difficulties
+            .iter()
+            .find(|difficulty| difficulty.key == *key)
+            .map(|difficulty| difficulty.stats.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn stats_mut(&mut self, key: LeaderboardKey) -> &mut GameStats {
+        let index = match self.difficulties.iter().position(|d| d.key == key) {
+            Some(index) => index,
+            None => {
+                self.difficulties.push(DifficultyStats {
+                    key,
+                    stats: GameStats::default(),
+                });
+                self.difficulties.len() - 1
+            }
+        };
+        &mut self.difficulties[index].stats
+    }
+
+    /// Stats of every board setting together
+    pub fn total(&self) -> GameStats {
+        let mut total = GameStats::default();
+        for difficulty in &self.difficulties {
+            total.merge(&difficulty.stats);
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::BoardOptions;
+
+    #[test]
+    fn streaks() {
+        let mut stats = GameStats::default();
+        stats.record_win(Duration::from_secs(10));
+        stats.record_win(Duration::from_secs(20));
+        stats.record_loss();
+        stats.record_win(Duration::from_secs(30));
+        assert_eq!((stats.played, stats.won, stats.lost), (4, 3, 1));
+        assert_eq!(stats.current_streak, 1);
+        assert_eq!(stats.best_streak, 2);
+        assert_eq!(stats.average_time(), Some(Duration::from_secs(20)));
+        assert_eq!(stats.win_rate(), Some(0.75));
+    }
+
+    #[test]
+    fn accuracy() {
+        let mut stats = GameStats::default();
+        assert_eq!(stats.accuracy(), None);
+        assert_eq!(stats.average_time(), None);
+        stats.flags_placed = 4;
+        stats.correct_flags = 3;
+        assert_eq!(stats.accuracy(), Some(0.75));
+    }
+
+    #[test]
+    fn total_merges_every_setting() {
+        let mut stats = PlayerStats {
+            path: PathBuf::new(),
+            difficulties: Vec::new(),
+        };
+        let classic = LeaderboardKey::new(&BoardOptions::default());
+        let undo = LeaderboardKey::new(&BoardOptions {
+            history: true,
+            ..Default::default()
+        });
+        let game = stats.stats_mut(classic.clone());
+        game.record_win(Duration::from_secs(10));
+        game.record_win(Duration::from_secs(10));
+        game.record_win(Duration::from_secs(10));
+        game.record_loss();
+        game.flags_placed = 2;
+        game.correct_flags = 1;
+        let game = stats.stats_mut(undo.clone());
+        game.record_win(Duration::from_secs(30));
+        game.flags_placed = 2;
+        game.correct_flags = 2;
+
+        assert_eq!(stats.stats(&classic).played, 4);
+        assert_eq!(stats.stats(&undo).played, 1);
+        let total = stats.total();
+        assert_eq!((total.played, total.won, total.lost), (5, 4, 1));
+        // streaks keep their best value instead of adding up
+        assert_eq!(total.current_streak, 1);
+        assert_eq!(total.best_streak, 3);
+        assert_eq!(total.total_time, Duration::from_secs(60));
+        assert_eq!(total.accuracy(), Some(0.75));
+    }
+
+    #[test]
+    fn save_and_open() {
+        let dir = std::env::temp_dir().join(format!("stats-test-{}", std::process::id()));
+        let path = dir.join("stats.ron");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut stats = PlayerStats::open(&path).unwrap();
+        assert!(stats.difficulties.is_empty());
+        let key = LeaderboardKey::new(&BoardOptions::default());
+        stats
+            .stats_mut(key.clone())
+            .record_win(Duration::from_millis(1500));
+        stats.save().unwrap();
+
+        let opened = PlayerStats::open(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(opened.difficulties.len(), 1);
+        assert_eq!(opened.stats(&key), stats.stats(&key));
+    }
+}
@@ -0,0 +1,372 @@
+use crate::resources::{Board, BoardOptions};
+use crate::Coordinates;
+
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+
+/// Version of the replay format written by `Replay::write`
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Player input recorded in a replay
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Serialize)]
+pub enum ReplayAction {
+    Trigger(Coordinates),
+    Mark(Coordinates),
+    Chord(Coordinates),
+    Undo,
+    Redo,
+    /// Cursor position relative to the bottom left of the board, in tiles
+    Cursor(Vec2),
+}
+
+/// Recorded input with the time it happened at
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReplayEvent {
+    /// Time since the board was created, pauses excluded
+    pub time: Duration,
+    pub action: ReplayAction,
+}
+
+/// Inputs of a game from the creation of its board
+///
+/// The options hold the seed of the board, so playing the inputs back on a
+/// board created from them gives the same game
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Replay {
+    pub version: u32,
+    pub options: BoardOptions,
+    pub seed: u64,
+    pub events: Vec<ReplayEvent>,
+}
+
+/// Failure to read or write a replay file
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+    UnsupportedVersion(u32),
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "replay file access failed: {e}"),
+            Self::Serialize(e) => write!(f, "failed to serialize replay: {e}"),
+            Self::Deserialize(e) => write!(f, "invalid replay file: {e}"),
+            Self::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "unsupported replay version {v}, expected {REPLAY_VERSION}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    /// Empty replay of a freshly created `board`
+    pub fn new(board: &Board) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            options: BoardOptions {
+                seed: Some(board.seed),
+                ..board.options.clone()
+            },
+            seed: board.seed,
+            events: Vec::new(),
+        }
+    }
+
+    /// Total duration, up to the last recorded input
+    pub fn duration(&self) -> Duration {
+        self.events.last().map_or(Duration::ZERO, |e| e.time)
+    }
+
+    /// Reads a replay file, rejecting other format versions
+    pub fn read(path: &Path) -> Result<Self, ReplayError> {
+        #[derive(Deserialize)]
+        struct Header {
+            version: u32,
+        }
+
+        let content = std::fs::read_to_string(path).map_err(ReplayError::Io)?;
+        let header: Header = ron::from_str(&content).map_err(ReplayError::Deserialize)?;
+        if header.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(header.version));
+        }
+        ron::from_str(&content).map_err(ReplayError::Deserialize)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), ReplayError> {
+        let content =
+            ron::ser::to_string_pretty(self, Default::default()).map_err(ReplayError::Serialize)?;
+        std::fs::write(path, content).map_err(ReplayError::Io)
+    }
+}
+
+/// Records the inputs on the current board
+///
+/// Boards restored from a save file are recorded from the restored state and
+/// can't be played back faithfully
+#[derive(Debug, Clone, Resource)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+    elapsed: Duration,
+}
+
+impl ReplayRecorder {
+    pub fn new(board: &Board) -> Self {
+        Self {
+            replay: Replay::new(board),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Advances the recording time by `delta`
+    pub fn tick(&mut self, delta: Duration) {
+        self.elapsed += delta;
+    }
+
+    pub fn record(&mut self, action: ReplayAction) {
+        self.replay.events.push(ReplayEvent {
+            time: self.elapsed,
+            action,
+        });
+    }
+}
+
+/// Plays a replay back, disabling the mouse input on the board
+///
+/// Inserted by `ReplayPlugin` on a `PlayReplayEvent`, the next board is created
+/// from the replay options
+#[derive(Debug, Clone, Resource)]
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    position: Duration,
+    /// Index of the next event to play
+    next: usize,
+    speed: f32,
+    paused: bool,
+    /// Last played cursor position
+    cursor: Option<Vec2>,
+    /// True until the board is recreated for the playback
+    restart: bool,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            position: Duration::ZERO,
+            next: 0,
+            speed: 1.,
+            paused: false,
+            cursor: None,
+            restart: true,
+        }
+    }
+
+    /// Playback time since the board was created
+    pub fn position(&self) -> Duration {
+        self.position
+    }
+
+    /// Last played cursor position, relative to the bottom left of the board in tiles
+    pub fn cursor(&self) -> Option<Vec2> {
+        self.cursor
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Moves the playback to `position`, the board needs to be recreated
+    /// when moving backwards
+    pub fn seek(&mut self, position: Duration) {
+        if position < self.position {
+            self.next = 0;
+            self.cursor = None;
+            self.restart = true;
+        }
+        self.position = position;
+    }
+
+    /// Return true if the board must be recreated before playing events
+    pub fn needs_restart(&self) -> bool {
+        self.restart
+    }
+
+    /// Acknowledges the board was recreated
+    pub fn restarted(&mut self) {
+        self.restart = false;
+    }
+
+    /// Advances the playback time by `delta` scaled by the speed, unless paused
+    pub fn tick(&mut self, delta: Duration) {
+        if !self.paused {
+            self.position += delta.mul_f32(self.speed);
+        }
+    }
+
+    /// Takes the next events due at the current position, stopping before an
+    /// event of another kind than the previous tile input so that inputs handled
+    /// by different systems keep their order
+    pub fn due_events(&mut self) -> Vec<ReplayAction> {
+        let mut actions: Vec<ReplayAction> = Vec::new();
+        let mut kind = None;
+        while let Some(event) = self.replay.events.get(self.next) {
+            if event.time > self.position {
+                break;
+            }
+            if let ReplayAction::Cursor(position) = event.action {
+                self.cursor = Some(position);
+            } else {
+                let event_kind = std::mem::discriminant(&event.action);
+                if kind.is_some_and(|kind| kind != event_kind) {
+                    break;
+                }
+                kind = Some(event_kind);
+            }
+            actions.push(event.action);
+            self.next += 1;
+        }
+        actions
+    }
+
+    /// Takes the undo and redo events due at the current position, played
+    /// even when the game is over since undoing can resume it
+    pub fn due_history_events(&mut self) -> Vec<ReplayAction> {
+        let mut actions = Vec::new();
+        while let Some(event) = self.replay.events.get(self.next) {
+            if event.time > self.position
+                || !matches!(event.action, ReplayAction::Undo | ReplayAction::Redo)
+            {
+                break;
+            }
+            actions.push(event.action);
+            self.next += 1;
+        }
+        actions
+    }
+
+    /// Return true once every event was played
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.events.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(actions: &[(u64, ReplayAction)]) -> ReplayPlayer {
+        ReplayPlayer::new(Replay {
+            version: REPLAY_VERSION,
+            options: BoardOptions::default(),
+            seed: 0,
+            events: actions
+                .iter()
+                .map(|(millis, action)| ReplayEvent {
+                    time: Duration::from_millis(*millis),
+                    action: *action,
+                })
+                .collect(),
+        })
+    }
+
+    const A: Coordinates = Coordinates { x: 0, y: 0 };
+    const B: Coordinates = Coordinates { x: 1, y: 0 };
+
+    #[test]
+    fn due_events_batch_one_kind() {
+        let mut player = player(&[
+            (0, ReplayAction::Trigger(A)),
+            (0, ReplayAction::Cursor(Vec2::ONE)),
+            (10, ReplayAction::Trigger(B)),
+            (10, ReplayAction::Mark(A)),
+            (10, ReplayAction::Chord(B)),
+            (50, ReplayAction::Trigger(A)),
+        ]);
+        player.tick(Duration::from_millis(20));
+        assert_eq!(
+            player.due_events(),
+            [
+                ReplayAction::Trigger(A),
+                ReplayAction::Cursor(Vec2::ONE),
+                ReplayAction::Trigger(B)
+            ]
+        );
+        assert_eq!(player.due_events(), [ReplayAction::Mark(A)]);
+        assert_eq!(player.due_events(), [ReplayAction::Chord(B)]);
+        assert!(player.due_events().is_empty());
+        assert!(!player.is_finished());
+    }
+
+    #[test]
+    fn due_events_track_the_cursor() {
+        let mut player = player(&[
+            (0, ReplayAction::Cursor(Vec2::ZERO)),
+            (10, ReplayAction::Cursor(Vec2::new(2., 3.))),
+            (30, ReplayAction::Cursor(Vec2::ONE)),
+        ]);
+        assert_eq!(player.cursor(), None);
+        player.tick(Duration::from_millis(10));
+        assert_eq!(player.due_events().len(), 2);
+        assert_eq!(player.cursor(), Some(Vec2::new(2., 3.)));
+    }
+
+    #[test]
+    fn due_history_events_stop_at_other_inputs() {
+        let mut player = player(&[
+            (0, ReplayAction::Undo),
+            (0, ReplayAction::Redo),
+            (0, ReplayAction::Trigger(A)),
+            (0, ReplayAction::Undo),
+        ]);
+        assert_eq!(
+            player.due_history_events(),
+            [ReplayAction::Undo, ReplayAction::Redo]
+        );
+        assert!(player.due_history_events().is_empty());
+        assert_eq!(player.due_events(), [ReplayAction::Trigger(A)]);
+        assert_eq!(player.due_history_events(), [ReplayAction::Undo]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn seeking_backwards_restarts() {
+        let mut player = player(&[
+            (0, ReplayAction::Cursor(Vec2::ONE)),
+            (10, ReplayAction::Trigger(A)),
+        ]);
+        player.restarted();
+        player.tick(Duration::from_millis(20));
+        assert_eq!(player.due_events().len(), 2);
+        assert!(player.is_finished());
+
+        player.seek(Duration::from_millis(30));
+        assert!(!player.needs_restart());
+        player.seek(Duration::from_millis(5));
+        assert!(player.needs_restart());
+        assert_eq!(player.cursor(), None);
+        assert_eq!(player.due_events(), [ReplayAction::Cursor(Vec2::ONE)]);
+    }
+}
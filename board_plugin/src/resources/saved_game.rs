@@ -0,0 +1,251 @@
+use crate::resources::{Board, BoardOptions, BoardOptionsError, ClickCounts, GameClock, TileMap};
+use crate::{Coordinates, TileMark};
+
+use bevy::prelude::*;
+use bevy::utils::{Duration, HashMap};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+
+/// Version of the save format written by `SavedGame::write`
+pub const SAVE_VERSION: u32 = 1;
+
+/// Serializable state of an in-progress game
+///
+/// Inserted as a resource, the next `create_board` rebuilds it instead of
+/// generating a fresh tile map
+#[derive(Debug, Clone, Deserialize, Serialize, Resource)]
+pub struct SavedGame {
+    pub version: u32,
+    pub options: BoardOptions,
+    pub seed: u64,
+    pub generated: bool,
+    pub tile_map: TileMap,
+    pub revealed: Vec<Coordinates>,
+    /// Flagged tiles, repeated once per flag
+    pub flags: Vec<Coordinates>,
+    /// Tiles flagged as negative mines, repeated once per flag
+    #[serde(default)]
+    pub anti_flags: Vec<Coordinates>,
+    /// Question marked tiles, absent from saves made before question marks
+    #[serde(default)]
+    pub questions: Vec<Coordinates>,
+    pub elapsed: Duration,
+    pub clicks: ClickCounts,
+}
+
+/// Failure to read or write a save file
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+    UnsupportedVersion(u32),
+    InvalidOptions(BoardOptionsError),
+    /// The tile map rows don't match its size
+    InvalidTileMap,
+    OutOfBounds(Coordinates),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "save file access failed: {e}"),
+            Self::Serialize(e) => write!(f, "failed to serialize game: {e}"),
+            Self::Deserialize(e) => write!(f, "invalid save file: {e}"),
+            Self::UnsupportedVersion(v) => {
+                write!(f, "unsupported save version {v}, expected {SAVE_VERSION}")
+            }
+            Self::InvalidOptions(e) => write!(f, "invalid saved options: {e}"),
+            Self::InvalidTileMap => write!(f, "saved tile map rows don't match its size"),
+            Self::OutOfBounds(coords) => write!(f, "saved tile {coords} is outside of the map"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl SavedGame {
+    /// Captures the current state of `board`
+    pub fn new(board: &Board, clock: &GameClock) -> Self {
+        let mut revealed: Vec<_> = board.core.revealed_tiles().collect();
+        let mut flags: Vec<_> = board
+            .core
+            .marks()
+            .filter(|(_, mark)| matches!(mark, TileMark::Flag(_)))
+            .flat_map(|(c, mark)| std::iter::repeat_n(*c, mark.flag_count() as usize))
+            .collect();
+        let mut anti_flags: Vec<_> = board
+            .core
+            .marks()
+            .filter(|(_, mark)| matches!(mark, TileMark::AntiFlag(_)))
+            .flat_map(|(c, mark)| std::iter::repeat_n(*c, mark.flag_count() as usize))
+            .collect();
+        let mut questions: Vec<_> = board
+            .core
+            .marks()
+            .filter(|(_, mark)| *mark == TileMark::Question)
+            .map(|(c, _)| *c)
+            .collect();
+        revealed.sort();
+        flags.sort();
+        anti_flags.sort();
+        questions.sort();
+        Self {
+            version: SAVE_VERSION,
+            options: board.options.clone(),
+            seed: board.seed,
+            generated: board.generated,
+            tile_map: board.tile_map().clone(),
+            revealed,
+            flags,
+            anti_flags,
+            questions,
+            elapsed: clock.elapsed(),
+            clicks: board.clicks,
+        }
+    }
+
+    /// Marks of the saved covered tiles
+    pub fn marks(&self) -> impl Iterator<Item = (Coordinates, TileMark)> + '_ {
+        let flags = Self::count_flags(&self.flags)
+            .into_iter()
+            .map(|(c, count)| (c, TileMark::Flag(count)));
+        let anti_flags = Self::count_flags(&self.anti_flags)
+            .into_iter()
+            .map(|(c, count)| (c, TileMark::AntiFlag(count)));
+        let questions = self.questions.iter().map(|c| (*c, TileMark::Question));
+        flags.chain(anti_flags).chain(questions)
+    }
+
+    /// Number of flags per tile
+    fn count_flags(flags: &[Coordinates]) -> HashMap<Coordinates, u8> {
+        let mut counts: HashMap<Coordinates, u8> = HashMap::new();
+        for coords in flags {
+            *counts.entry(*coords).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Checks the options, tile map size and saved tiles, save files may be
+    /// edited or corrupt
+    pub fn validate(&self) -> Result<(), SaveError> {
+        self.options.validate().map_err(SaveError::InvalidOptions)?;
+        if !self.tile_map.has_consistent_size() {
+            return Err(SaveError::InvalidTileMap);
+        }
+        let (width, height) = (self.tile_map.width(), self.tile_map.height());
+        let out_of_bounds = self
+            .revealed
+            .iter()
+            .chain(self.flags.iter())
+            .chain(self.anti_flags.iter())
+            .chain(self.questions.iter())
+            .find(|c| c.x >= width || c.y >= height);
+        match out_of_bounds {
+            Some(coords) => Err(SaveError::OutOfBounds(*coords)),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads and validates a save file, rejecting other format versions
+    pub fn read(path: &Path) -> Result<Self, SaveError> {
+        #[derive(Deserialize)]
+        struct Header {
+            version: u32,
+        }
+
+        let content = std::fs::read_to_string(path).map_err(SaveError::Io)?;
+        let header: Header = ron::from_str(&content).map_err(SaveError::Deserialize)?;
+        if header.version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(header.version));
+        }
+        let saved: Self = ron::from_str(&content).map_err(SaveError::Deserialize)?;
+        saved.validate()?;
+        Ok(saved)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SaveError> {
+        let content =
+            ron::ser::to_string_pretty(self, Default::default()).map_err(SaveError::Serialize)?;
+        std::fs::write(path, content).map_err(SaveError::Io)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_game() -> SavedGame {
+        let mut tile_map = TileMap::empty(4, 3);
+        tile_map.add_bombs_at(&[Coordinates { x: 3, y: 2 }]);
+        SavedGame {
+            version: SAVE_VERSION,
+            options: BoardOptions {
+                map_size: (4, 3),
+                bomb_count: 1,
+                ..Default::default()
+            },
+            seed: 7,
+            generated: true,
+            tile_map,
+            revealed: vec![Coordinates { x: 0, y: 0 }],
+            flags: vec![Coordinates { x: 3, y: 2 }],
+            anti_flags: Vec::new(),
+            questions: vec![Coordinates { x: 1, y: 2 }],
+            elapsed: Duration::from_secs(12),
+            clicks: ClickCounts {
+                left: 1,
+                right: 1,
+                chord: 0,
+            },
+        }
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{name}-{}.sav", std::process::id()))
+    }
+
+    #[test]
+    fn write_and_read() {
+        let path = temp_path("saved-game-test");
+        let saved = saved_game();
+        saved.write(&path).unwrap();
+        let read = SavedGame::read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(*read.tile_map, *saved.tile_map);
+        assert_eq!(read.revealed, saved.revealed);
+        assert_eq!(read.marks().count(), 2);
+        assert_eq!(read.elapsed, saved.elapsed);
+        assert_eq!(read.clicks, saved.clicks);
+    }
+
+    #[test]
+    fn malformed_saves_are_rejected() {
+        let mut out_of_bounds = saved_game();
+        out_of_bounds.flags.push(Coordinates { x: 4, y: 0 });
+        assert!(matches!(
+            out_of_bounds.validate(),
+            Err(SaveError::OutOfBounds(Coordinates { x: 4, y: 0 }))
+        ));
+
+        let mut invalid_options = saved_game();
+        invalid_options.options.bomb_count = 100;
+        assert!(matches!(
+            invalid_options.validate(),
+            Err(SaveError::InvalidOptions(_))
+        ));
+
+        // rows shorter than the declared width
+        let path = temp_path("malformed-save-test");
+        let content = ron::ser::to_string(&saved_game())
+            .unwrap()
+            .replace("width:4", "width:5");
+        std::fs::write(&path, content).unwrap();
+        let read = SavedGame::read(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(read, Err(SaveError::InvalidTileMap)));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::components::Coordinates;
+
+use bevy::render::render_resource::TextureFormat;
+use bevy::render::texture::{CompressedImageFormats, Image, ImageSampler, ImageType, TextureError};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Playable tiles of an irregular board, the other tiles of its bounding
+/// rectangle are holes
+///
+/// Serialized as ASCII rows from top to bottom, `#` marking tiles and `.` holes
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct ShapeMask {
+    width: u16,
+    height: u16,
+    /// Row by row from the bottom left, true for playable tiles
+    tiles: Vec<bool>,
+}
+
+/// Shape mask of `BoardOptions`, given inline or as the path of a mask file
+///
+/// Serialized as the ASCII rows of the mask or as the path string
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MaskSource {
+    Inline(ShapeMask),
+    /// PNG or ASCII file read by `ShapeMask::load` when the board is created
+    File(PathBuf),
+}
+
+impl MaskSource {
+    /// The mask if it is inline, `None` for a file that wasn't read yet
+    pub fn shape(&self) -> Option<&ShapeMask> {
+        match self {
+            Self::Inline(mask) => Some(mask),
+            Self::File(_) => None,
+        }
+    }
+}
+
+/// Failure to read a shape mask
+#[derive(Debug)]
+pub enum MaskError {
+    Io(std::io::Error),
+    Image(TextureError),
+    UnsupportedPixelFormat,
+    InvalidCharacter(char),
+    TooLarge,
+    NoTiles,
+}
+
+impl Display for MaskError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "mask file access failed: {e}"),
+            Self::Image(e) => write!(f, "invalid mask image: {e}"),
+            Self::UnsupportedPixelFormat => write!(f, "unsupported mask image pixel format"),
+            Self::InvalidCharacter(c) => {
+                write!(f, "invalid mask character {c:?}, expected '#' or '.'")
+            }
+            Self::TooLarge => write!(f, "mask is larger than {} tiles per side", u16::MAX),
+            Self::NoTiles => write!(f, "mask has no playable tile"),
+        }
+    }
+}
+
+impl std::error::Error for MaskError {}
+
+impl ShapeMask {
+    /// Parses ASCII rows from top to bottom, `#` marking tiles and `.` or
+    /// spaces holes. Shorter rows are padded with holes
+    pub fn from_ascii(text: &str) -> Result<Self, MaskError> {
+        let rows = text
+            .lines()
+            .map(|line| {
+                line.trim_end()
+                    .chars()
+                    .map(|c| match c {
+                        '#' => Ok(true),
+                        '.' | ' ' => Ok(false),
+                        c => Err(MaskError::InvalidCharacter(c)),
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<bool>>, _>>()?;
+        Self::from_rows(rows)
+    }
+
+    /// Decodes a PNG image, opaque and bright pixels marking tiles
+    pub fn from_png(bytes: &[u8]) -> Result<Self, MaskError> {
+        let image = Image::from_buffer(
+            bytes,
+            ImageType::Extension("png"),
+            CompressedImageFormats::NONE,
+            true,
+            ImageSampler::Default,
+        )
+        .map_err(MaskError::Image)?;
+        let width = image.width() as usize;
+        if width == 0 {
+            return Err(MaskError::NoTiles);
+        }
+        let image = image
+            .convert(TextureFormat::Rgba8UnormSrgb)
+            .ok_or(MaskError::UnsupportedPixelFormat)?;
+        let rows = image
+            .data
+            .chunks_exact(width * 4)
+            .map(|row| {
+                row.chunks_exact(4)
+                    .map(|pixel| pixel[3] >= 128 && pixel[..3].iter().any(|c| *c >= 128))
+                    .collect()
+            })
+            .collect();
+        Self::from_rows(rows)
+    }
+
+    /// Reads a PNG mask if `path` has a `png` extension, an ASCII mask otherwise
+    pub fn load(path: &Path) -> Result<Self, MaskError> {
+        let bytes = std::fs::read(path).map_err(MaskError::Io)?;
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+        {
+            Self::from_png(&bytes)
+        } else {
+            Self::from_ascii(&String::from_utf8_lossy(&bytes))
+        }
+    }
+
+    /// Builds a mask from rows ordered from top to bottom
+    fn from_rows(mut rows: Vec<Vec<bool>>) -> Result<Self, MaskError> {
+        // trailing blank lines don't count towards the height
+        while rows.last().is_some_and(|row| !row.contains(&true)) {
+            rows.pop();
+        }
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if width == 0 {
+            return Err(MaskError::NoTiles);
+        }
+        let (Ok(w), Ok(h)) = (u16::try_from(width), u16::try_from(rows.len())) else {
+            return Err(MaskError::TooLarge);
+        };
+        let tiles = rows
+            .iter()
+            .rev()
+            .flat_map(|row| (0..width).map(|x| row.get(x).copied().unwrap_or(false)))
+            .collect();
+        Ok(Self {
+            width: w,
+            height: h,
+            tiles,
+        })
+    }
+
+    /// Width and height of the bounding rectangle
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Number of playable tiles
+    pub fn tile_count(&self) -> u32 {
+        self.tiles.iter().filter(|tile| **tile).count() as u32
+    }
+
+    /// Hash of the size and tiles of the mask, stable across builds and platforms
+    pub fn fingerprint(&self) -> u64 {
+        // 64 bit FNV-1a
+        let size = [self.width.to_le_bytes(), self.height.to_le_bytes()].concat();
+        let tiles = self.tiles.iter().map(|tile| *tile as u8);
+        size.into_iter()
+            .chain(tiles)
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    /// Return true if `coords` is a playable tile
+    pub fn is_tile(&self, coords: Coordinates) -> bool {
+        coords.x < self.width
+            && coords.y < self.height
+            && self.tiles[coords.y as usize * self.width as usize + coords.x as usize]
+    }
+}
+
+impl TryFrom<Vec<String>> for ShapeMask {
+    type Error = MaskError;
+
+    fn try_from(rows: Vec<String>) -> Result<Self, Self::Error> {
+        Self::from_ascii(&rows.join("\n"))
+    }
+}
+
+impl From<ShapeMask> for Vec<String> {
+    fn from(mask: ShapeMask) -> Self {
+        (0..mask.height)
+            .rev()
+            .map(|y| {
+                (0..mask.width)
+                    .map(|x| match mask.is_tile(Coordinates { x, y }) {
+                        true => '#',
+                        false => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_rows_are_padded_with_holes() {
+        let mask = ShapeMask::from_ascii("###\n#\n. #").unwrap();
+        assert_eq!(mask.size(), (3, 3));
+        assert_eq!(mask.tile_count(), 5);
+        assert_eq!(Vec::<String>::from(mask), ["###", "#..", "..#"]);
+    }
+
+    #[test]
+    fn trailing_blank_rows_are_dropped() {
+        let mask = ShapeMask::from_ascii("#.#\n.#.\n...\n\n  ").unwrap();
+        assert_eq!(mask.size(), (3, 2));
+        assert!(mask.is_tile(Coordinates { x: 1, y: 0 }));
+    }
+
+    #[test]
+    fn invalid_masks_are_rejected() {
+        assert!(matches!(
+            ShapeMask::from_ascii("#.\n#x"),
+            Err(MaskError::InvalidCharacter('x'))
+        ));
+        assert!(matches!(
+            ShapeMask::from_ascii("..\n"),
+            Err(MaskError::NoTiles)
+        ));
+    }
+
+    #[test]
+    fn is_tile() {
+        // rows are given from the top, coordinates start at the bottom left
+        let mask = ShapeMask::from_ascii("#.\n.#\n#.").unwrap();
+        assert!(mask.is_tile(Coordinates { x: 0, y: 0 }));
+        assert!(!mask.is_tile(Coordinates { x: 1, y: 0 }));
+        assert!(mask.is_tile(Coordinates { x: 1, y: 1 }));
+        assert!(mask.is_tile(Coordinates { x: 0, y: 2 }));
+        assert!(!mask.is_tile(Coordinates { x: 2, y: 0 }));
+        assert!(!mask.is_tile(Coordinates { x: 0, y: 3 }));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let mask = ShapeMask::from_ascii(".#.\n###").unwrap();
+        let ron = ron::to_string(&mask).unwrap();
+        assert_eq!(ron, "[\".#.\",\"###\"]");
+        assert_eq!(ron::from_str::<ShapeMask>(&ron).unwrap(), mask);
+        assert!(ron::from_str::<ShapeMask>("[\"#?\"]").is_err());
+    }
+
+    #[test]
+    fn mask_source_forms() {
+        let inline: MaskSource = ron::from_str("[\"##\", \"#.\"]").unwrap();
+        assert_eq!(inline.shape().map(ShapeMask::tile_count), Some(3));
+        let file: MaskSource = ron::from_str("\"masks/heart.png\"").unwrap();
+        assert_eq!(file, MaskSource::File("masks/heart.png".into()));
+        assert!(file.shape().is_none());
+    }
+}
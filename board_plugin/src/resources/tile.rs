@@ -1,17 +1,46 @@
 #[cfg(feature = "debug")]
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
 
 /// Minesweeper tile
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
 pub enum Tile {
-    Bomb,
-    Neighbor(u8),
+    /// Number of mines hidden in the tile
+    Bomb(u8),
+    /// Number of negative mines hidden in the tile
+    AntiBomb(u8),
+    /// Sum of the neighboring mines, negative mines subtracting from it
+    Neighbor(i8),
     Empty,
+    /// Missing tile of an irregular board
+    Hole,
 }
 
 impl Tile {
+    /// Return true for tiles hiding mines of either sign
     pub const fn is_bomb(&self) -> bool {
-        matches!(self, Self::Bomb)
+        matches!(self, Self::Bomb(_) | Self::AntiBomb(_))
+    }
+
+    pub const fn is_anti_bomb(&self) -> bool {
+        matches!(self, Self::AntiBomb(_))
+    }
+
+    /// Number of mines hidden in the tile, regardless of their sign
+    pub const fn mine_count(&self) -> u8 {
+        match self {
+            Self::Bomb(count) | Self::AntiBomb(count) => *count,
+            _ => 0,
+        }
+    }
+
+    /// Contribution of the tile to the counters around it
+    pub fn mine_value(&self) -> i8 {
+        match self {
+            Self::Bomb(count) => i8::try_from(*count).unwrap_or(i8::MAX),
+            Self::AntiBomb(count) => -i8::try_from(*count).unwrap_or(i8::MAX),
+            _ => 0,
+        }
     }
 
     #[cfg(feature = "debug")]
@@ -19,14 +48,19 @@ impl Tile {
         format!(
             "{}",
             match self {
-                Tile::Bomb => "*".bright_red(),
+                Tile::Bomb(1) => "*".bright_red(),
+                Tile::Bomb(v) => v.to_string().bright_red(),
+                Tile::AntiBomb(1) => "+".bright_blue(),
+                Tile::AntiBomb(v) => format!("-{v}").bright_blue(),
                 Tile::Neighbor(v) => match v {
                     1 => "1".cyan(),
                     2 => "2".green(),
                     3 => "3".yellow(),
+                    ..=0 => v.to_string().blue(),
                     _ => v.to_string().red(),
                 },
                 Tile::Empty => " ".normal(),
+                Tile::Hole => "#".dimmed(),
             }
         )
     }
@@ -1,45 +1,59 @@
 use crate::components::Coordinates;
 use crate::resources::tile::Tile;
+use crate::resources::{Neighborhood, ShapeMask, Topology};
 
-use rand::{thread_rng, Rng};
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
 use std::ops::{Deref, DerefMut};
 
-/// Delta coordinates for all 8 square neighbors
+/// Version of the seeded mine placement of `TileMap::add_bombs`
 ///
-/// *--------*-------*-------*
-/// | -1, 1  | 0, 1  | 1, 1  |
-/// |--------|-------|-------|
-/// | -1, 0  | tile  | 1, 0  |
-/// |--------|-------|-------|
-/// | -1, -1 | 0, -1 | 1, -1 |
-/// *--------*-------*-------*
-///
-const NEIGHBOR_OFFSETS: [(i8, i8); 8] = [
-    // bottom left
-    (-1, -1),
-    // bottom
-    (0, -1),
-    // bottom right
-    (1, -1),
-    // left
-    (-1, 0),
-    // right
-    (1, 0),
-    // top left
-    (-1, 1),
-    // top
-    (0, 1),
-    // top right
-    (1, 1),
-];
+/// Version 1 drew random tiles until enough were empty. Version 2 shuffles
+/// the candidate tiles to support stacked and negative mines, so seeds of
+/// version 1 give different maps
+pub const PLACEMENT_VERSION: u32 = 2;
+
+/// Failure to place the requested mines
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PlacementError {
+    /// The allowed tiles can't hold every mine
+    NotEnoughTiles { mines: u16, available: usize },
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::NotEnoughTiles { mines, available } => {
+                write!(f, "{mines} mines don't fit, only {available} can be placed")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
 
 /// Base tile map
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TileMap {
     bomb_count: u16,
+    #[serde(default)]
+    anti_bomb_count: u16,
     height: u16,
     width: u16,
     map: Vec<Vec<Tile>>,
+    #[serde(default)]
+    topology: Topology,
+    #[serde(default)]
+    neighborhood: Neighborhood,
+    #[serde(default = "default_max_mines")]
+    max_mines: u8,
+}
+
+fn default_max_mines() -> u8 {
+    1
 }
 
 impl TileMap {
@@ -47,14 +61,59 @@ impl TileMap {
     pub fn empty(width: u16, height: u16) -> Self {
         Self {
             bomb_count: 0,
+            anti_bomb_count: 0,
             height,
             width,
             map: (0..height)
                 .map(|_| (0..width).map(|_| Tile::Empty).collect())
                 .collect(),
+            topology: Topology::Square,
+            neighborhood: Neighborhood::Moore,
+            max_mines: 1,
         }
     }
 
+    /// Sets the topology used to find the neighbors of tiles
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// Turns the tiles missing from `mask` into holes
+    pub fn with_mask(mut self, mask: &ShapeMask) -> Self {
+        for (y, line) in self.map.iter_mut().enumerate() {
+            for (x, tile) in line.iter_mut().enumerate() {
+                let coords = Coordinates {
+                    x: x as u16,
+                    y: y as u16,
+                };
+                if !mask.is_tile(coords) {
+                    *tile = Tile::Hole;
+                }
+            }
+        }
+        self
+    }
+
+    /// Sets the neighbors of square tiles
+    pub fn with_neighborhood(mut self, neighborhood: Neighborhood) -> Self {
+        self.neighborhood = neighborhood;
+        self
+    }
+
+    /// Sets how many negative mines the next `add_bombs` places along the
+    /// regular ones
+    pub fn with_anti_bombs(mut self, count: u16) -> Self {
+        self.anti_bomb_count = count;
+        self
+    }
+
+    /// Sets how many mines a single tile can hide
+    pub fn with_max_mines(mut self, max_mines: u8) -> Self {
+        self.max_mines = max_mines.max(1);
+        self
+    }
+
     #[cfg(feature = "debug")]
     pub fn console_output(&self) -> String {
         let mut buffer = format!(
@@ -74,6 +133,14 @@ impl TileMap {
         format!("{}{}", buffer, line)
     }
 
+    /// Returns true if the rows match `width` and `height`, which deserialized
+    /// maps don't guarantee
+    pub fn has_consistent_size(&self) -> bool {
+        self.width > 0
+            && self.map.len() == self.height as usize
+            && self.map.iter().all(|row| row.len() == self.width as usize)
+    }
+
     pub fn width(&self) -> u16 {
         self.width
     }
@@ -82,15 +149,96 @@ impl TileMap {
         self.height
     }
 
+    /// Total number of mines
     pub fn bomb_count(&self) -> u16 {
         self.bomb_count
     }
 
-    pub fn surrounding_tiles(&self, coordinates: Coordinates) -> impl Iterator<Item = Coordinates> {
-        NEIGHBOR_OFFSETS
+    /// Total number of negative mines
+    pub fn anti_bomb_count(&self) -> u16 {
+        self.anti_bomb_count
+    }
+
+    /// Maximum number of mines in a single tile
+    pub fn max_mines(&self) -> u8 {
+        self.max_mines
+    }
+
+    /// Number of mines hidden in the tile at `coordinates`
+    pub fn mines_at(&self, coordinates: Coordinates) -> u8 {
+        if coordinates.x >= self.width || coordinates.y >= self.height {
+            0
+        } else {
+            self.map[coordinates.y as usize][coordinates.x as usize].mine_count()
+        }
+    }
+
+    /// Number of tiles, holes excluded
+    pub fn tile_count(&self) -> usize {
+        self.iter()
+            .flatten()
+            .filter(|tile| **tile != Tile::Hole)
+            .count()
+    }
+
+    pub fn is_hole(&self, coordinates: Coordinates) -> bool {
+        coordinates.x < self.width
+            && coordinates.y < self.height
+            && self.map[coordinates.y as usize][coordinates.x as usize] == Tile::Hole
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn neighborhood(&self) -> &Neighborhood {
+        &self.neighborhood
+    }
+
+    /// Iterates over the in-bounds neighbors of `coordinates`, holes excluded, following the
+    /// topology and neighborhood, wrapping across the edges if the topology does.
+    /// Offsets wrapping to the same tile or back to `coordinates` on small boards count once
+    pub fn surrounding_tiles(
+        &self,
+        coordinates: Coordinates,
+    ) -> impl Iterator<Item = Coordinates> + '_ {
+        let (width, height) = (self.width as i32, self.height as i32);
+        let wraps = self.topology.wraps();
+        let mut wrapped = HashSet::new();
+        let fixed = self.topology.neighbor_offsets(coordinates);
+        let neighborhood = fixed.is_none().then(|| self.neighborhood.offsets());
+        fixed
+            .unwrap_or_default()
             .iter()
             .copied()
-            .map(move |offset| coordinates + offset)
+            .chain(neighborhood.into_iter().flatten())
+            .filter_map(move |(dx, dy)| {
+                let x = coordinates.x as i32 + dx as i32;
+                let y = coordinates.y as i32 + dy as i32;
+                if wraps {
+                    Some((x.rem_euclid(width), y.rem_euclid(height)))
+                } else {
+                    ((0..width).contains(&x) && (0..height).contains(&y)).then_some((x, y))
+                }
+            })
+            .map(|(x, y)| Coordinates {
+                x: x as u16,
+                y: y as u16,
+            })
+            .filter(move |c| !wraps || (*c != coordinates && wrapped.insert(*c)))
+            .filter(|c| !self.is_hole(*c))
+    }
+
+    /// Retrieves the first empty tile, row by row from the bottom left
+    pub fn first_empty_tile(&self) -> Option<Coordinates> {
+        self.iter().enumerate().find_map(|(y, line)| {
+            line.iter()
+                .position(|tile| *tile == Tile::Empty)
+                .map(|x| Coordinates {
+                    x: x as u16,
+                    y: y as u16,
+                })
+        })
     }
 
     pub fn is_bomb_at(&self, coordinates: Coordinates) -> bool {
@@ -101,48 +249,185 @@ impl TileMap {
         }
     }
 
-    pub fn bomb_count_at(&self, coordinates: Coordinates) -> u8 {
+    /// Contribution of the tile at `coordinates` to the counters around it,
+    /// negative for negative mines
+    pub fn mine_value_at(&self, coordinates: Coordinates) -> i8 {
+        if coordinates.x >= self.width || coordinates.y >= self.height {
+            0
+        } else {
+            self.map[coordinates.y as usize][coordinates.x as usize].mine_value()
+        }
+    }
+
+    /// Sum of the mines in the neighbors of a safe tile, negative mines
+    /// subtracting from it
+    pub fn bomb_count_at(&self, coordinates: Coordinates) -> i8 {
         if self.is_bomb_at(coordinates) {
             0
         } else {
             self.surrounding_tiles(coordinates)
-                .filter(|neighbor| self.is_bomb_at(*neighbor))
-                .count() as u8
+                .fold(0, |count: i8, neighbor| {
+                    count.saturating_add(self.mine_value_at(neighbor))
+                })
         }
     }
 
-    pub fn add_bombs(&mut self, count: u16) {
-        self.bomb_count = count;
-        let mut remaining = count;
-        let mut rng = thread_rng();
-
-        while remaining > 0 {
-            let (x, y) = (
-                rng.gen_range(0..self.width) as usize,
-                rng.gen_range(0..self.height) as usize,
-            );
-
-            if let Tile::Empty = self[y][x] {
-                self[y][x] = Tile::Bomb;
-                remaining -= 1;
+    /// Places `count` bombs and the negative mines set by `with_anti_bombs`
+    /// using a generator seeded with `seed`, stacking up to `max_mines` mines
+    /// of the same sign in a tile
+    ///
+    /// The same map size, bomb count and seed always yield the same map for a
+    /// given `PLACEMENT_VERSION`. Fails if the map can't hold every mine
+    pub fn add_bombs(&mut self, count: u16, seed: u64) -> Result<(), PlacementError> {
+        self.place_bombs(count, seed, |_| true)
+    }
+
+    /// Places `count` bombs like `add_bombs`, keeping every tile within
+    /// `radius` neighbor steps of `safe` clear of bombs
+    pub fn add_bombs_excluding(
+        &mut self,
+        count: u16,
+        seed: u64,
+        safe: Coordinates,
+        radius: u8,
+    ) -> Result<(), PlacementError> {
+        let excluded = self.tiles_within(safe, radius);
+        self.place_bombs(count, seed, |coords| !excluded.contains(&coords))
+    }
+
+    /// Tiles reachable from `center` in at most `radius` neighbor steps
+    fn tiles_within(&self, center: Coordinates, radius: u8) -> HashSet<Coordinates> {
+        let mut tiles = HashSet::from([center]);
+        let mut ring = vec![center];
+        for _ in 0..radius {
+            ring = ring
+                .iter()
+                .flat_map(|c| self.surrounding_tiles(*c))
+                .filter(|c| tiles.insert(*c))
+                .collect();
+        }
+        tiles
+    }
+
+    /// Places a mine on each of `mines`, repeated coordinates stacking up to
+    /// `max_mines` mines. Replaces any previous layout, including the
+    /// negative mines set by `with_anti_bombs`
+    pub fn add_bombs_at(&mut self, mines: &[Coordinates]) {
+        for line in self.map.iter_mut() {
+            for tile in line.iter_mut().filter(|tile| **tile != Tile::Hole) {
+                *tile = Tile::Empty;
             }
         }
+        self.bomb_count = 0;
+        self.anti_bomb_count = 0;
+        for coords in mines {
+            if coords.x >= self.width
+                || coords.y >= self.height
+                || self.is_hole(*coords)
+                || self.mines_at(*coords) >= self.max_mines
+            {
+                continue;
+            }
+            let tile = &mut self[coords.y as usize][coords.x as usize];
+            *tile = Tile::Bomb(tile.mine_count() + 1);
+            self.bomb_count += 1;
+        }
+        self.update_counters();
+    }
+
+    /// Places a negative mine on each of `anti_mines` after `add_bombs_at`,
+    /// skipping the tiles hiding regular mines
+    pub fn add_anti_bombs_at(&mut self, anti_mines: &[Coordinates]) {
+        for coords in anti_mines {
+            if coords.x >= self.width
+                || coords.y >= self.height
+                || self.is_hole(*coords)
+                || self.mines_at(*coords) >= self.max_mines
+                || matches!(self[coords.y as usize][coords.x as usize], Tile::Bomb(_))
+            {
+                continue;
+            }
+            let tile = &mut self[coords.y as usize][coords.x as usize];
+            *tile = Tile::AntiBomb(tile.mine_count() + 1);
+            self.anti_bomb_count += 1;
+        }
+        self.update_counters();
+    }
+
+    /// Places `count` bombs on the tiles accepted by `allowed`, then the
+    /// negative mines on the remaining ones
+    fn place_bombs(
+        &mut self,
+        count: u16,
+        seed: u64,
+        allowed: impl Fn(Coordinates) -> bool,
+    ) -> Result<(), PlacementError> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
 
+        let bombs = self.mine_candidates(&allowed, &mut rng, count)?;
+        self.bomb_count = count;
+        for coords in bombs {
+            let tile = &mut self[coords.y as usize][coords.x as usize];
+            *tile = Tile::Bomb(tile.mine_count() + 1);
+        }
+
+        // negative mines never share a tile with regular ones
+        let anti_bombs = self.mine_candidates(
+            |coords| !self.is_bomb_at(coords) && allowed(coords),
+            &mut rng,
+            self.anti_bomb_count,
+        )?;
+        for coords in anti_bombs {
+            let tile = &mut self[coords.y as usize][coords.x as usize];
+            *tile = Tile::AntiBomb(tile.mine_count() + 1);
+        }
+        self.update_counters();
+        Ok(())
+    }
+
+    /// Sets the counter of every safe tile from the mines around it
+    fn update_counters(&mut self) {
         for y in 0..self.height {
             for x in 0..self.width {
                 let coord = Coordinates { x, y };
-                if self.is_bomb_at(coord) {
+                if self.is_bomb_at(coord) || self.is_hole(coord) {
                     continue;
                 }
 
-                let num = self.bomb_count_at(coord);
-
-                if num > 0 {
-                    self.map[y as usize][x as usize] = Tile::Neighbor(num);
+                // mines of opposite signs can cancel out, leaving a zero counter
+                if self
+                    .surrounding_tiles(coord)
+                    .any(|neighbor| self.is_bomb_at(neighbor))
+                {
+                    self.map[y as usize][x as usize] = Tile::Neighbor(self.bomb_count_at(coord));
                 }
             }
         }
     }
+
+    /// Picks `count` mine locations among the tiles accepted by `allowed`
+    fn mine_candidates(
+        &self,
+        allowed: impl Fn(Coordinates) -> bool,
+        rng: &mut ChaCha8Rng,
+        count: u16,
+    ) -> Result<Vec<Coordinates>, PlacementError> {
+        // every tile is a candidate once per mine it can hide
+        let max_mines = self.max_mines as usize;
+        let mut candidates: Vec<Coordinates> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Coordinates { x, y }))
+            .filter(|coords| !self.is_hole(*coords) && allowed(*coords))
+            .flat_map(|coords| std::iter::repeat_n(coords, max_mines))
+            .collect();
+        if candidates.len() < count as usize {
+            return Err(PlacementError::NotEnoughTiles {
+                mines: count,
+                available: candidates.len(),
+            });
+        }
+        let (mines, _) = candidates.partial_shuffle(rng, count as usize);
+        Ok(mines.to_vec())
+    }
 }
 
 impl Deref for TileMap {
@@ -158,3 +443,50 @@ impl DerefMut for TileMap {
         &mut self.map
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_map() {
+        let mut first = TileMap::empty(16, 16);
+        first.add_bombs(40, 1234).unwrap();
+        let mut second = TileMap::empty(16, 16);
+        second.add_bombs(40, 1234).unwrap();
+
+        assert_eq!(first.bomb_count(), 40);
+        assert_eq!(*first, *second);
+    }
+
+    #[test]
+    fn small_torus_neighbors_are_unique() {
+        let tile_map =
+            TileMap::empty(2, 2).with_topology(Topology::Toroidal { ghost_edges: false });
+        let mut neighbors: Vec<_> = tile_map
+            .surrounding_tiles(Coordinates { x: 0, y: 0 })
+            .collect();
+        neighbors.sort();
+
+        assert_eq!(
+            neighbors,
+            [
+                Coordinates { x: 0, y: 1 },
+                Coordinates { x: 1, y: 0 },
+                Coordinates { x: 1, y: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn too_many_bombs_fail() {
+        let mut tile_map = TileMap::empty(3, 3);
+        assert_eq!(
+            tile_map.add_bombs(10, 0),
+            Err(PlacementError::NotEnoughTiles {
+                mines: 10,
+                available: 9
+            })
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use crate::components::Coordinates;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Delta coordinates for the 6 hexagonal neighbors in axial coordinates
+const AXIAL_NEIGHBOR_OFFSETS: [(i8, i8); 6] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, -1), (-1, 1)];
+
+/// Neighbors of pointy hexagons on even rows, odd rows are shifted right
+const EVEN_ROW_NEIGHBOR_OFFSETS: [(i8, i8); 6] =
+    [(1, 0), (-1, 0), (0, 1), (-1, 1), (0, -1), (-1, -1)];
+
+/// Neighbors of pointy hexagons on odd rows
+const ODD_ROW_NEIGHBOR_OFFSETS: [(i8, i8); 6] = [(1, 0), (-1, 0), (1, 1), (0, 1), (1, -1), (0, -1)];
+
+/// Neighbors of flat hexagons on even columns, odd columns are shifted up
+const EVEN_COLUMN_NEIGHBOR_OFFSETS: [(i8, i8); 6] =
+    [(0, 1), (0, -1), (1, 0), (-1, 0), (1, -1), (-1, -1)];
+
+/// Neighbors of flat hexagons on odd columns
+const ODD_COLUMN_NEIGHBOR_OFFSETS: [(i8, i8); 6] =
+    [(0, 1), (0, -1), (1, 0), (-1, 0), (1, 1), (-1, 1)];
+
+/// Orientation of hexagonal tiles
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum HexOrientation {
+    /// Vertices at the top and bottom, tiles are laid in rows
+    #[default]
+    Pointy,
+    /// Edges at the top and bottom, tiles are laid in columns
+    Flat,
+}
+
+/// Mapping of hexagonal tiles to tile map coordinates
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum HexCoordinates {
+    /// Odd rows (pointy) or columns (flat) are shifted by half a tile,
+    /// giving a rectangular board
+    #[default]
+    Offset,
+    /// Tile map coordinates are axial hex coordinates, giving a rhombus
+    /// shaped board
+    Axial,
+}
+
+/// Shape of the tiles and how they connect
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Topology {
+    /// Square tiles
+    #[default]
+    Square,
+    /// Hexagonal tiles with 6 neighbors, `tile_size` is the distance
+    /// between two opposite edges. The board `Neighborhood` is ignored
+    Hexagonal {
+        orientation: HexOrientation,
+        coordinates: HexCoordinates,
+    },
+    /// Square tiles wrapping across opposite edges, every tile has a full
+    /// neighborhood. Boards need to be wider than the neighborhood for
+    /// neighbors to be distinct
+    Toroidal {
+        /// Displays copies of the opposite edges around the board
+        ghost_edges: bool,
+    },
+}
+
+impl Topology {
+    /// Delta coordinates of the neighbors of `coords`, `None` for square
+    /// tiles whose neighbors follow the board `Neighborhood`
+    pub fn neighbor_offsets(&self, coords: Coordinates) -> Option<&'static [(i8, i8)]> {
+        match *self {
+            Self::Square | Self::Toroidal { .. } => None,
+            Self::Hexagonal {
+                coordinates: HexCoordinates::Axial,
+                ..
+            } => Some(&AXIAL_NEIGHBOR_OFFSETS),
+            Self::Hexagonal { orientation, .. } => {
+                match (orientation, coords.x % 2, coords.y % 2) {
+                    (HexOrientation::Pointy, _, 0) => Some(&EVEN_ROW_NEIGHBOR_OFFSETS),
+                    (HexOrientation::Pointy, _, _) => Some(&ODD_ROW_NEIGHBOR_OFFSETS),
+                    (HexOrientation::Flat, 0, _) => Some(&EVEN_COLUMN_NEIGHBOR_OFFSETS),
+                    (HexOrientation::Flat, _, _) => Some(&ODD_COLUMN_NEIGHBOR_OFFSETS),
+                }
+            }
+        }
+    }
+
+    /// Returns true if neighbors wrap across the edges of the board
+    pub fn wraps(&self) -> bool {
+        matches!(self, Self::Toroidal { .. })
+    }
+
+    /// Size of the bounding box of a tile sprite
+    pub fn tile_extent(&self, tile_size: f32) -> Vec2 {
+        match self {
+            Self::Square | Self::Toroidal { .. } => Vec2::splat(tile_size),
+            Self::Hexagonal { orientation, .. } => {
+                let diameter = Self::hex_diameter(tile_size);
+                match orientation {
+                    HexOrientation::Pointy => Vec2::new(tile_size, diameter),
+                    HexOrientation::Flat => Vec2::new(diameter, tile_size),
+                }
+            }
+        }
+    }
+
+    /// Center of the tile at `coords`, relative to the bottom left of the board
+    pub fn tile_center(&self, coords: Coordinates, tile_size: f32) -> Vec2 {
+        let (x, y) = (coords.x as f32, coords.y as f32);
+        let Self::Hexagonal {
+            orientation,
+            coordinates,
+        } = *self
+        else {
+            return Vec2::new(x + 0.5, y + 0.5) * tile_size;
+        };
+        // rows (pointy) or columns (flat) are three quarters of a diameter apart
+        let diameter = Self::hex_diameter(tile_size);
+        match (orientation, coordinates) {
+            (HexOrientation::Pointy, HexCoordinates::Offset) => Vec2::new(
+                (x + 0.5 + 0.5 * (coords.y % 2) as f32) * tile_size,
+                (0.5 + 0.75 * y) * diameter,
+            ),
+            (HexOrientation::Pointy, HexCoordinates::Axial) => {
+                Vec2::new((x + 0.5 + 0.5 * y) * tile_size, (0.5 + 0.75 * y) * diameter)
+            }
+            (HexOrientation::Flat, HexCoordinates::Offset) => Vec2::new(
+                (0.5 + 0.75 * x) * diameter,
+                (y + 0.5 + 0.5 * (coords.x % 2) as f32) * tile_size,
+            ),
+            (HexOrientation::Flat, HexCoordinates::Axial) => {
+                Vec2::new((0.5 + 0.75 * x) * diameter, (y + 0.5 + 0.5 * x) * tile_size)
+            }
+        }
+    }
+
+    /// Size of a board of `width` by `height` tiles
+    pub fn board_size(&self, (width, height): (u16, u16), tile_size: f32) -> Vec2 {
+        let (w, h) = (width as f32, height as f32);
+        let Self::Hexagonal {
+            orientation,
+            coordinates,
+        } = *self
+        else {
+            return Vec2::new(w, h) * tile_size;
+        };
+        let diameter = Self::hex_diameter(tile_size);
+        // extra half tile taken by the shifted rows or columns
+        let shift = |other: f32| match coordinates {
+            HexCoordinates::Offset if other > 1. => 0.5,
+            HexCoordinates::Offset => 0.,
+            HexCoordinates::Axial => 0.5 * (other - 1.).max(0.),
+        };
+        match orientation {
+            HexOrientation::Pointy => {
+                Vec2::new((w + shift(h)) * tile_size, (0.25 + 0.75 * h) * diameter)
+            }
+            HexOrientation::Flat => {
+                Vec2::new((0.25 + 0.75 * w) * diameter, (h + shift(w)) * tile_size)
+            }
+        }
+    }
+
+    /// Coordinates of the tile under `position`, relative to the bottom left
+    /// of the board. The coordinates may be out of the tile map bounds
+    pub fn coordinates_at(&self, position: Vec2, tile_size: f32) -> Option<Coordinates> {
+        let Self::Hexagonal {
+            orientation,
+            coordinates,
+        } = *self
+        else {
+            let coords = (position / tile_size).floor();
+            return Self::to_coordinates(coords.x as i32, coords.y as i32);
+        };
+        let diameter = Self::hex_diameter(tile_size);
+        // fractional axial coordinates
+        let (q, r) = match orientation {
+            HexOrientation::Pointy => {
+                let r = (position.y - 0.5 * diameter) / (0.75 * diameter);
+                ((position.x - 0.5 * tile_size) / tile_size - 0.5 * r, r)
+            }
+            HexOrientation::Flat => {
+                let q = (position.x - 0.5 * diameter) / (0.75 * diameter);
+                (q, (position.y - 0.5 * tile_size) / tile_size - 0.5 * q)
+            }
+        };
+        let (q, r) = Self::round_axial(q, r);
+        match (orientation, coordinates) {
+            (_, HexCoordinates::Axial) => Self::to_coordinates(q, r),
+            (HexOrientation::Pointy, HexCoordinates::Offset) => {
+                Self::to_coordinates(q + (r - (r & 1)) / 2, r)
+            }
+            (HexOrientation::Flat, HexCoordinates::Offset) => {
+                Self::to_coordinates(q, r + (q - (q & 1)) / 2)
+            }
+        }
+    }
+
+    /// Vertex to vertex diameter of a hexagon of `tile_size` edge to edge
+    fn hex_diameter(tile_size: f32) -> f32 {
+        tile_size * 2. / 3_f32.sqrt()
+    }
+
+    /// Rounds fractional axial coordinates to the nearest hexagon
+    fn round_axial(q: f32, r: f32) -> (i32, i32) {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        (rq as i32, rr as i32)
+    }
+
+    fn to_coordinates(x: i32, y: i32) -> Option<Coordinates> {
+        Some(Coordinates {
+            x: x.try_into().ok()?,
+            y: y.try_into().ok()?,
+        })
+    }
+}
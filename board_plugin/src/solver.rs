@@ -0,0 +1,389 @@
+use crate::components::Coordinates;
+use crate::resources::tile::Tile;
+use crate::resources::tile_map::{PlacementError, TileMap};
+
+use bevy::utils::{Duration, Instant};
+
+/// Solver knowledge of a tile
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Cell {
+    Covered,
+    Revealed,
+    /// Known to be full of mines, negative for negative mines
+    Flagged(i8),
+}
+
+/// Covered tiles around a revealed counter and the sum of the mines they still hide
+#[derive(Debug, Clone)]
+struct Constraint {
+    covered: Vec<Coordinates>,
+    bombs: i32,
+}
+
+/// Deterministic minesweeper solver which never guesses
+///
+/// Only uses the information a player would have: revealed counters and the
+/// total bomb counts
+#[derive(Debug, Clone)]
+pub struct Solver<'a> {
+    tile_map: &'a TileMap,
+    cells: Vec<Cell>,
+    /// Mines in the flagged tiles
+    flagged: usize,
+    /// Negative mines in the flagged tiles
+    anti_flagged: usize,
+    remaining_safe: usize,
+}
+
+impl<'a> Solver<'a> {
+    /// Starts solving a fully covered `tile_map`
+    pub fn new(tile_map: &'a TileMap) -> Self {
+        // holes are known from the start
+        let cells = tile_map
+            .iter()
+            .flatten()
+            .map(|tile| match tile {
+                Tile::Hole => Cell::Revealed,
+                _ => Cell::Covered,
+            })
+            .collect();
+        Self {
+            tile_map,
+            cells,
+            flagged: 0,
+            anti_flagged: 0,
+            remaining_safe: tile_map.tile_count()
+                - tile_map
+                    .iter()
+                    .flatten()
+                    .filter(|tile| tile.is_bomb())
+                    .count(),
+        }
+    }
+
+    /// Return true once every safe tile is revealed
+    pub fn is_solved(&self) -> bool {
+        self.remaining_safe == 0
+    }
+
+    /// Reveals `coords`, flooding through empty tiles. Returns false on a bomb
+    pub fn reveal(&mut self, coords: Coordinates) -> bool {
+        if self.tile(coords).is_bomb() {
+            return false;
+        }
+        let mut stack = vec![coords];
+        while let Some(coords) = stack.pop() {
+            let index = self.index(coords);
+            if self.cells[index] != Cell::Covered {
+                continue;
+            }
+            self.cells[index] = Cell::Revealed;
+            self.remaining_safe -= 1;
+            if *self.tile(coords) == Tile::Empty {
+                stack.extend(self.tile_map.surrounding_tiles(coords));
+            }
+        }
+        true
+    }
+
+    /// Reveals the first covered safe tile, preferring empty tiles, as a
+    /// lucky guess would. Returns false if none is left
+    pub fn guess(&mut self) -> bool {
+        let covered = self.covered_tiles();
+        let safe = covered.iter().filter(|c| !self.tile(**c).is_bomb());
+        let guess = safe
+            .clone()
+            .find(|c| *self.tile(**c) == Tile::Empty)
+            .or_else(|| safe.clone().next())
+            .copied();
+        guess.is_some_and(|coords| self.reveal(coords))
+    }
+
+    /// Applies deductions until the board is solved or no safe move is left.
+    /// Returns true if the board is solved
+    pub fn solve(&mut self) -> bool {
+        while !self.is_solved() && self.step() {}
+        self.is_solved()
+    }
+
+    /// Runs a single round of deductions, returning true if any progress was made
+    ///
+    /// Tiles are only flagged once known to hide as many mines as a tile can
+    fn step(&mut self) -> bool {
+        let constraints = self.constraints();
+        let remaining_bombs = self.tile_map.bomb_count() as usize - self.flagged;
+        let remaining_anti_bombs = self.tile_map.anti_bomb_count() as usize - self.anti_flagged;
+        // range of the value of a single covered tile
+        let max_mines = self.tile_map.max_mines() as i8;
+        let range = (
+            if remaining_anti_bombs > 0 {
+                -max_mines
+            } else {
+                0
+            },
+            if remaining_bombs > 0 { max_mines } else { 0 },
+        );
+        let (mut safe, mut bombs) = (Vec::new(), Vec::new());
+
+        // Trivial constraints: all bombs found, or every covered tile is full of bombs
+        for constraint in constraints.iter() {
+            match Self::deduce(constraint.covered.len(), constraint.bombs, range) {
+                Some(0) => safe.extend_from_slice(&constraint.covered),
+                Some(value) => bombs.extend(constraint.covered.iter().map(|c| (*c, value))),
+                None => (),
+            }
+        }
+
+        // Subset constraints: the tiles of `b` outside of `a` hide the difference
+        if safe.is_empty() && bombs.is_empty() {
+            for a in constraints.iter() {
+                for b in constraints.iter() {
+                    if a.covered.len() >= b.covered.len()
+                        || !a.covered.iter().all(|c| b.covered.contains(c))
+                    {
+                        continue;
+                    }
+                    let rest = b.covered.iter().filter(|c| !a.covered.contains(c));
+                    let count = b.covered.len() - a.covered.len();
+                    match Self::deduce(count, b.bombs - a.bombs, range) {
+                        Some(0) => safe.extend(rest),
+                        Some(value) => bombs.extend(rest.map(|c| (*c, value))),
+                        None => (),
+                    }
+                }
+            }
+        }
+
+        // Global constraint: the remaining bomb counts
+        if safe.is_empty() && bombs.is_empty() {
+            let covered = self.covered_tiles();
+            let capacity = covered.len() * max_mines as usize;
+            match (remaining_bombs, remaining_anti_bombs) {
+                (0, 0) => safe = covered,
+                (n, 0) if n == capacity => {
+                    bombs = covered.iter().map(|c| (*c, max_mines)).collect()
+                }
+                (0, n) if n == capacity => {
+                    bombs = covered.iter().map(|c| (*c, -max_mines)).collect()
+                }
+                _ => (),
+            }
+        }
+
+        let mut progress = false;
+        for (coords, value) in bombs {
+            let index = self.index(coords);
+            if self.cells[index] == Cell::Covered {
+                self.cells[index] = Cell::Flagged(value);
+                match value > 0 {
+                    true => self.flagged += max_mines as usize,
+                    false => self.anti_flagged += max_mines as usize,
+                }
+                progress = true;
+            }
+        }
+        for coords in safe {
+            if self.cells[self.index(coords)] == Cell::Covered {
+                self.reveal(coords);
+                progress = true;
+            }
+        }
+        progress
+    }
+
+    /// Value of every tile of a group of `count` covered tiles whose values
+    /// add up to `sum`, if it can be deduced. Tile values are within `range`
+    fn deduce(count: usize, sum: i32, (min, max): (i8, i8)) -> Option<i8> {
+        let count = count as i32;
+        if sum == 0 && (min == 0 || max == 0) {
+            Some(0)
+        } else if max > 0 && sum == count * max as i32 {
+            Some(max)
+        } else if min < 0 && sum == count * min as i32 {
+            Some(min)
+        } else {
+            None
+        }
+    }
+
+    /// Builds the constraints of every revealed counter still touching covered tiles
+    fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints = Vec::new();
+        for coords in self.tiles() {
+            let count = match (self.cells[self.index(coords)], self.tile(coords)) {
+                (Cell::Revealed, Tile::Neighbor(count)) => *count as i32,
+                _ => continue,
+            };
+            let mut covered = Vec::new();
+            let mut flagged = 0;
+            for neighbor in self.tile_map.surrounding_tiles(coords) {
+                match self.cells[self.index(neighbor)] {
+                    Cell::Covered => covered.push(neighbor),
+                    Cell::Flagged(value) => flagged += value as i32,
+                    Cell::Revealed => (),
+                }
+            }
+            if !covered.is_empty() {
+                constraints.push(Constraint {
+                    covered,
+                    bombs: count - flagged,
+                });
+            }
+        }
+        constraints
+    }
+
+    fn covered_tiles(&self) -> Vec<Coordinates> {
+        self.tiles()
+            .filter(|c| self.cells[self.index(*c)] == Cell::Covered)
+            .collect()
+    }
+
+    fn tiles(&self) -> impl Iterator<Item = Coordinates> {
+        let (width, height) = (self.tile_map.width(), self.tile_map.height());
+        (0..height).flat_map(move |y| (0..width).map(move |x| Coordinates { x, y }))
+    }
+
+    fn tile(&self, coords: Coordinates) -> &Tile {
+        &self.tile_map[coords.y as usize][coords.x as usize]
+    }
+
+    fn index(&self, coords: Coordinates) -> usize {
+        coords.y as usize * self.tile_map.width() as usize + coords.x as usize
+    }
+}
+
+/// Return true if `tile_map` can be cleared from `start` without guessing
+pub fn is_solvable_from(tile_map: &TileMap, start: Coordinates) -> bool {
+    let mut solver = Solver::new(tile_map);
+    solver.reveal(start) && solver.solve()
+}
+
+/// Generates layouts until one can be cleared without guessing
+///
+/// `template` is the empty tile map to fill, giving the size, topology and neighborhood.
+/// `start` is the first click and its safe radius if already known, otherwise
+/// the first empty tile of each layout is used as starting point.
+/// Each attempt derives its seed from `seed`, so results are reproducible unless
+/// `max_duration` runs out first.
+/// Returns the last layout and false if the budget ran out, fails if the
+/// mines don't fit on the template
+pub fn generate_no_guess(
+    template: &TileMap,
+    bomb_count: u16,
+    seed: u64,
+    start: Option<(Coordinates, u8)>,
+    max_attempts: u32,
+    max_duration: Duration,
+) -> Result<(TileMap, bool), PlacementError> {
+    let started = Instant::now();
+    let mut attempt = 0;
+    loop {
+        let mut tile_map = template.clone();
+        let attempt_seed = seed.wrapping_add(attempt as u64);
+        let start = match start {
+            Some((coords, radius)) => {
+                tile_map.add_bombs_excluding(bomb_count, attempt_seed, coords, radius)?;
+                Some(coords)
+            }
+            None => {
+                tile_map.add_bombs(bomb_count, attempt_seed)?;
+                tile_map.first_empty_tile()
+            }
+        };
+
+        if start.is_some_and(|start| is_solvable_from(&tile_map, start)) {
+            return Ok((tile_map, true));
+        }
+        attempt += 1;
+        if attempt >= max_attempts || started.elapsed() >= max_duration {
+            return Ok((tile_map, false));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(x: u16, y: u16) -> Coordinates {
+        Coordinates { x, y }
+    }
+
+    fn tile_map(width: u16, height: u16, mines: &[Coordinates]) -> TileMap {
+        let mut tile_map = TileMap::empty(width, height);
+        tile_map.add_bombs_at(mines);
+        tile_map
+    }
+
+    #[test]
+    fn trivial_deductions() {
+        // * 1 #, the counter is full so the bomb is flagged
+        let mut full = TileMap::empty(3, 1);
+        full[0][2] = Tile::Hole;
+        full.add_bombs_at(&[coords(0, 0)]);
+        let mut solver = Solver::new(&full);
+        assert!(solver.reveal(coords(1, 0)));
+        assert!(solver.step());
+        assert_eq!(solver.cells[solver.index(coords(0, 0))], Cell::Flagged(1));
+
+        // * 1 _ 1 *, the counter is satisfied so the tile next to it is safe
+        let satisfied = tile_map(5, 1, &[coords(0, 0), coords(4, 0)]);
+        let mut solver = Solver::new(&satisfied);
+        solver.cells[0] = Cell::Flagged(1);
+        solver.flagged = 1;
+        assert!(solver.reveal(coords(1, 0)));
+        assert!(solver.step());
+        assert_eq!(solver.cells[solver.index(coords(2, 0))], Cell::Revealed);
+        assert_eq!(solver.cells[solver.index(coords(3, 0))], Cell::Revealed);
+        assert_eq!(solver.cells[solver.index(coords(4, 0))], Cell::Covered);
+    }
+
+    #[test]
+    fn subset_deduction() {
+        // * ? ?
+        // 1 1 ?
+        let tile_map = tile_map(3, 2, &[coords(0, 1)]);
+        let mut solver = Solver::new(&tile_map);
+        assert!(solver.reveal(coords(0, 0)));
+        assert!(solver.reveal(coords(1, 0)));
+
+        // the bomb of the left counter is also the one of the right counter
+        assert!(solver.step());
+        assert_eq!(solver.cells[solver.index(coords(2, 0))], Cell::Revealed);
+        assert_eq!(solver.cells[solver.index(coords(2, 1))], Cell::Revealed);
+        assert_eq!(solver.cells[solver.index(coords(0, 1))], Cell::Covered);
+    }
+
+    #[test]
+    fn global_deduction() {
+        // * 1 # _, the hole hides the last tile from the counters
+        let mut tile_map = TileMap::empty(4, 1);
+        tile_map[0][2] = Tile::Hole;
+        tile_map.add_bombs_at(&[coords(0, 0)]);
+
+        // every bomb is flagged, the tile out of reach is safe
+        assert!(is_solvable_from(&tile_map, coords(1, 0)));
+    }
+
+    #[test]
+    fn guess_required() {
+        // * 1 _
+        // 1 1 _, the bomb can be on either tile of the left column
+        let tile_map = tile_map(3, 2, &[coords(0, 1)]);
+        assert!(!is_solvable_from(&tile_map, coords(2, 0)));
+    }
+
+    #[test]
+    fn no_guess_generation_is_deterministic() {
+        let template = TileMap::empty(9, 9);
+        let generate =
+            || generate_no_guess(&template, 10, 42, None, 1000, Duration::from_secs(60)).unwrap();
+        let (first, solvable) = generate();
+        let (second, _) = generate();
+
+        assert!(solvable);
+        assert!(is_solvable_from(&first, first.first_empty_tile().unwrap()));
+        assert_eq!(*first, *second);
+    }
+}
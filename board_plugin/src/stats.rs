@@ -0,0 +1,184 @@
+use crate::components::StatsScreen;
+use crate::events::{BoardCompletedEvent, BombExplosionEvent, ExportStatsEvent};
+use crate::resources::{
+    Board, BoardAssets, BoardOptions, GameStats, LeaderboardKey, PlayerStats, ReplayPlayer,
+};
+use crate::systems::{board_completed_handler, bomb_explosion_handler};
+
+use bevy::log;
+use bevy::prelude::*;
+
+/// Updates the `PlayerStats` resource inserted by the host after every
+/// finished game and lists them while in `screen_state`
+///
+/// Replays aren't counted, and each board only counts once
+pub struct StatsPlugin<T> {
+    pub screen_state: T,
+}
+
+impl<T: States> Plugin for StatsPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExportStatsEvent>()
+            .add_systems(
+                Update,
+                (
+                    // before the end of game handlers reveal the bombs and flag them
+                    Self::record_game
+                        .before(board_completed_handler)
+                        .before(bomb_explosion_handler)
+                        .run_if(resource_exists::<Board>()),
+                    Self::export_stats,
+                )
+                    .run_if(resource_exists::<PlayerStats>()),
+            )
+            .add_systems(
+                OnEnter(self.screen_state.clone()),
+                Self::spawn_screen
+                    .run_if(resource_exists::<PlayerStats>())
+                    .run_if(resource_exists::<BoardOptions>()),
+            )
+            .add_systems(OnExit(self.screen_state.clone()), Self::despawn_screen);
+    }
+}
+
+impl<T: States> StatsPlugin<T> {
+    fn record_game(
+        mut board: ResMut<Board>,
+        player: Option<Res<ReplayPlayer>>,
+        mut stats: ResMut<PlayerStats>,
+        mut board_completed_rdr: EventReader<BoardCompletedEvent>,
+        mut bomb_explosion_rdr: EventReader<BombExplosionEvent>,
+    ) {
+        let won = board_completed_rdr.read().last().map(|event| event.elapsed);
+        let lost = bomb_explosion_rdr.read().last().is_some();
+        if (won.is_none() && !lost) || board.restored || player.is_some() || board.recorded {
+            return;
+        }
+        board.recorded = true;
+        let game = stats.stats_mut(LeaderboardKey::new(&board.options));
+        match won {
+            Some(time) => game.record_win(time),
+            None => game.record_loss(),
+        }
+        let tile_map = board.tile_map();
+        game.tiles_revealed += board
+            .core
+            .revealed_tiles()
+            .filter(|coords| !tile_map.is_bomb_at(*coords))
+            .count() as u64;
+        game.flags_placed += board.core.flagged_tiles().count() as u64;
+        game.correct_flags += board
+            .core
+            .flagged_tiles()
+            .filter(|coords| board.core.mark(coords) == Some(board.core.full_flag(coords)))
+            .count() as u64;
+        if let Err(e) = stats.save() {
+            log::error!("Failed to save stats to {}: {e}", stats.path.display());
+        }
+    }
+
+    fn export_stats(stats: Res<PlayerStats>, mut export_stats_rdr: EventReader<ExportStatsEvent>) {
+        for ExportStatsEvent(path) in export_stats_rdr.read() {
+            match stats.export_json(path) {
+                Ok(()) => log::info!("Stats exported to {}", path.display()),
+                Err(e) => log::error!("Failed to export stats to {}: {e}", path.display()),
+            }
+        }
+    }
+
+    /// Spawns the stats of the current board, or of the board options without
+    /// one, and of every game
+    fn spawn_screen(
+        mut commands: Commands,
+        stats: Res<PlayerStats>,
+        options: Res<BoardOptions>,
+        board: Option<Res<Board>>,
+        board_assets: Res<BoardAssets>,
+    ) {
+        // the board options resolve mask and layout files
+        let options = board.as_ref().map_or(&*options, |board| &board.options);
+        let key = LeaderboardKey::new(options);
+        let style = TextStyle {
+            font: board_assets.bomb_counter_font.clone(),
+            font_size: 16.,
+            color: Color::WHITE,
+        };
+        let sections = [
+            (key.to_string(), stats.stats(&key)),
+            ("All games".to_string(), stats.total()),
+        ];
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.8).into(),
+                ..default()
+            })
+            .insert(Name::new("Stats"))
+            .insert(StatsScreen)
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    "Stats",
+                    TextStyle {
+                        font_size: 30.,
+                        ..style.clone()
+                    },
+                ));
+                for (title, game) in sections {
+                    parent.spawn(TextBundle::from_section(
+                        title,
+                        TextStyle {
+                            font_size: 20.,
+                            ..style.clone()
+                        },
+                    ));
+                    for line in stats_lines(&game) {
+                        parent.spawn(TextBundle::from_section(line, style.clone()));
+                    }
+                }
+            });
+    }
+
+    fn despawn_screen(mut commands: Commands, screens: Query<Entity, With<StatsScreen>>) {
+        for entity in screens.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Lines of the stats screen describing `stats`
+fn stats_lines(stats: &GameStats) -> Vec<String> {
+    let percent =
+        |ratio: Option<f32>| ratio.map_or("-".to_string(), |r| format!("{:.0}%", r * 100.));
+    vec![
+        format!(
+            "Played {} Won {} Lost {} ({})",
+            stats.played,
+            stats.won,
+            stats.lost,
+            percent(stats.win_rate())
+        ),
+        format!("Streak {} Best {}", stats.current_streak, stats.best_streak),
+        format!(
+            "Average time {}",
+            stats.average_time().map_or("-".to_string(), |time| format!(
+                "{:.2}s",
+                time.as_secs_f32()
+            ))
+        ),
+        format!(
+            "Tiles revealed {} Flags {} ({} accurate)",
+            stats.tiles_revealed,
+            stats.flags_placed,
+            percent(stats.accuracy())
+        ),
+    ]
+}
@@ -0,0 +1,36 @@
+use crate::resources::{GameClock, MineCounter};
+use crate::{Board, ClockText, MineCounterText};
+use bevy::prelude::*;
+
+/// Advances the clock, flagging it changed only when the displayed second changes
+pub fn tick_game_clock(time: Res<Time>, mut clock: ResMut<GameClock>) {
+    let seconds = clock.elapsed().as_secs();
+    clock.bypass_change_detection().tick(time.delta());
+    if clock.elapsed().as_secs() != seconds {
+        clock.set_changed();
+    }
+}
+
+pub fn update_mine_counter(board: Res<Board>, mut counter: ResMut<MineCounter>) {
+    if board.is_changed() {
+        counter.0 = board.remaining_mines();
+    }
+}
+
+pub fn update_hud(
+    clock: Res<GameClock>,
+    counter: Res<MineCounter>,
+    mut mine_texts: Query<&mut Text, (With<MineCounterText>, Without<ClockText>)>,
+    mut clock_texts: Query<&mut Text, (With<ClockText>, Without<MineCounterText>)>,
+) {
+    if counter.is_changed() {
+        for mut text in mine_texts.iter_mut() {
+            text.sections[0].value = format!("{:03}", counter.0);
+        }
+    }
+    if clock.is_changed() {
+        for mut text in clock_texts.iter_mut() {
+            text.sections[0].value = format!("{:03}", clock.elapsed().as_secs());
+        }
+    }
+}
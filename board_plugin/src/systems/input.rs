@@ -1,32 +1,54 @@
-use crate::events::{TileMarkEvent, TileTriggerEvent};
-use crate::Board;
+use crate::events::{TileChordEvent, TileMarkEvent, TileTriggerEvent};
+use crate::{Board, GameStatus};
 
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 
+/// Sends tile events from the mouse buttons. Left and right clicks happen on
+/// release, unless both buttons were pressed together to chord
 pub fn input_handling(
     windows: Query<&Window, With<PrimaryWindow>>,
     board: Res<Board>,
     input: Res<Input<MouseButton>>,
     mut tile_trigger: EventWriter<TileTriggerEvent>,
     mut mark_trigger: EventWriter<TileMarkEvent>,
+    mut chord_trigger: EventWriter<TileChordEvent>,
+    mut chording: Local<bool>,
 ) {
+    // the chord lasts until both buttons are released
+    let chorded = *chording;
+    if !input.any_pressed([MouseButton::Left, MouseButton::Right]) {
+        *chording = false;
+    }
+
+    if board.core.status() != GameStatus::Playing {
+        return;
+    }
     let window = windows.single();
 
     if let Some(pos) = window.cursor_position() {
         if let Some(coordinates) = board.mouse_position(window, pos) {
-            if input.just_pressed(MouseButton::Left) {
-                info!("uncover {coordinates}");
-                tile_trigger.send(TileTriggerEvent(coordinates));
-            }
-            if input.just_pressed(MouseButton::Right) {
-                info!("mark {coordinates}");
-                mark_trigger.send(TileMarkEvent(coordinates));
+            let bindings = board.options.chord_bindings;
+            let left_right = bindings.left_right_click
+                && input.pressed(MouseButton::Left)
+                && input.pressed(MouseButton::Right)
+                && input.any_just_pressed([MouseButton::Left, MouseButton::Right]);
+            let middle = bindings.middle_click && input.just_pressed(MouseButton::Middle);
+
+            if left_right || middle {
+                info!("chord {coordinates}");
+                chord_trigger.send(TileChordEvent(coordinates));
+                *chording |= left_right;
+            } else if !chorded {
+                if input.just_released(MouseButton::Left) {
+                    info!("uncover {coordinates}");
+                    tile_trigger.send(TileTriggerEvent(coordinates));
+                }
+                if input.just_released(MouseButton::Right) {
+                    info!("mark {coordinates}");
+                    mark_trigger.send(TileMarkEvent(coordinates));
+                }
             }
-            // if input.just_pressed(MouseButton::Middle) {
-            //     info!("hint {coordinates}");
-            //     // generate event
-            // }
         }
     }
 }
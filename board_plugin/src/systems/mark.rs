@@ -1,5 +1,5 @@
-use crate::{Board, BoardAssets, TileMarkEvent};
-use bevy::log;
+use crate::resources::SpriteMaterial;
+use crate::{Board, BoardAssets, TileMark, TileMarkEvent};
 use bevy::prelude::*;
 
 pub fn mark_tiles(
@@ -7,35 +7,63 @@ pub fn mark_tiles(
     mut board: ResMut<Board>,
     board_assets: Res<BoardAssets>,
     mut tile_mark_event_rdr: EventReader<TileMarkEvent>,
-    query: Query<&Children>,
 ) {
     for event in tile_mark_event_rdr.read() {
         if let Some((entity, mark)) = board.try_toggle_mark(&event.0) {
-            if mark {
-                commands.entity(entity).with_children(|parent| {
-                    parent.spawn(SpriteBundle {
-                        texture: board_assets.flag_material.texture.clone(),
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::splat(board.tile_size)),
-                            color: board_assets.flag_material.color,
-                            ..default()
-                        },
-                        transform: Transform::from_xyz(0., 0., 1.),
-                        ..default()
-                    });
-                });
-            } else {
-                let children = match query.get(entity) {
-                    Ok(c) => c,
-                    Err(e) => {
-                        log::error!("Failed to retrieve flag entity components: {e}");
-                        continue;
-                    }
-                };
-                for child in children.iter() {
-                    commands.entity(*child).despawn_recursive();
-                }
+            commands.entity(entity).despawn_descendants();
+            if let Some(mark) = mark {
+                spawn_mark(&mut commands, entity, &board_assets, mark, board.tile_size);
             }
         }
     }
 }
+
+/// Spawns the sprite of `mark` on the tile cover `entity`, labelled with the
+/// flag count when more than one mine is flagged
+pub(crate) fn spawn_mark(
+    commands: &mut Commands,
+    entity: Entity,
+    board_assets: &BoardAssets,
+    mark: TileMark,
+    size: f32,
+) {
+    spawn_flag(commands, entity, board_assets.mark_material(mark), size);
+    let count = mark.flag_count();
+    if count > 1 {
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn(Text2dBundle {
+                text: Text::from_section(
+                    count.to_string(),
+                    TextStyle {
+                        font: board_assets.bomb_counter_font.clone(),
+                        font_size: size / 2.,
+                        color: Color::WHITE,
+                    },
+                ),
+                transform: Transform::from_xyz(size / 4., -size / 4., 2.),
+                ..default()
+            });
+        });
+    }
+}
+
+/// Spawns a flag or question mark sprite on the tile cover `entity`
+pub(crate) fn spawn_flag(
+    commands: &mut Commands,
+    entity: Entity,
+    material: &SpriteMaterial,
+    size: f32,
+) {
+    commands.entity(entity).with_children(|parent| {
+        parent.spawn(SpriteBundle {
+            texture: material.texture.clone(),
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(size)),
+                color: material.color,
+                ..default()
+            },
+            transform: Transform::from_xyz(0., 0., 1.),
+            ..default()
+        });
+    });
+}
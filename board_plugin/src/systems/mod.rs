@@ -1,7 +1,11 @@
+mod hud;
 mod input;
 mod mark;
+mod save;
 mod uncover;
 
+pub use hud::*;
 pub use input::*;
 pub use mark::*;
+pub use save::*;
 pub use uncover::*;
@@ -0,0 +1,17 @@
+use crate::events::SaveGameEvent;
+use crate::resources::{GameClock, SavedGame};
+use crate::Board;
+use bevy::prelude::*;
+
+pub fn save_game(
+    board: Res<Board>,
+    clock: Res<GameClock>,
+    mut save_game_rdr: EventReader<SaveGameEvent>,
+) {
+    for SaveGameEvent(path) in save_game_rdr.read() {
+        match SavedGame::new(&board, &clock).write(path) {
+            Ok(()) => info!("Game saved to {}", path.display()),
+            Err(e) => error!("Failed to save game to {}: {e}", path.display()),
+        }
+    }
+}
@@ -1,59 +1,156 @@
+use super::mark::{spawn_flag, spawn_mark};
 use crate::{
-    events::{BoardCompletedEvent, BombExplosionEvent, TileTriggerEvent},
-    Board, Bomb, Coordinates, Neighbor, Uncover,
+    events::{BoardCompletedEvent, BombExplosionEvent, TileChordEvent, TileTriggerEvent},
+    metrics::BoardMetrics,
+    resources::{GameClock, SafeStart},
+    Board, BoardAssets, GameStatus, Uncover,
 };
 use bevy::prelude::*;
 
 pub fn trigger_event_handler(
     mut commands: Commands,
-    board: Res<Board>,
+    mut board: ResMut<Board>,
+    mut clock: ResMut<GameClock>,
     mut tile_trigger: EventReader<TileTriggerEvent>,
+    mut board_completed_event_wr: EventWriter<BoardCompletedEvent>,
+    mut bomb_explosion_event_wr: EventWriter<BombExplosionEvent>,
 ) {
     for trigger_event in tile_trigger.read() {
-        if let Some(entity) = board.tile_to_uncover(&trigger_event.0) {
-            commands.entity(*entity).insert(Uncover);
+        if awaits_generation(&board) {
+            continue;
         }
+        let covers = board.reveal(&trigger_event.0);
+        uncover(
+            &mut commands,
+            &mut board,
+            &mut clock,
+            covers,
+            &mut board_completed_event_wr,
+            &mut bomb_explosion_event_wr,
+        );
     }
 }
 
-pub fn uncover_tiles(
-    mut command: Commands,
+pub fn chord_event_handler(
+    mut commands: Commands,
     mut board: ResMut<Board>,
-    children: Query<(Entity, &Parent), With<Uncover>>,
-    parents: Query<(&Coordinates, Option<&Bomb>, Option<&Neighbor>)>,
+    mut clock: ResMut<GameClock>,
+    mut tile_chord: EventReader<TileChordEvent>,
     mut board_completed_event_wr: EventWriter<BoardCompletedEvent>,
-    mut _bomb_explosion_event_wr: EventWriter<BombExplosionEvent>,
+    mut bomb_explosion_event_wr: EventWriter<BombExplosionEvent>,
 ) {
-    for (entity, parent) in children.iter() {
-        // destroy the tile cover
-        command.entity(entity).despawn_recursive();
-
-        let (coords, bomb, bomb_counter) = match parents.get(parent.get()) {
-            Ok(v) => v,
-            Err(e) => {
-                error!("{e}");
-                continue;
-            }
-        };
-
-        match board.try_uncover_tile(coords) {
-            Some(e) => debug!("Uncovered tile {coords} (entity: {e:?}"),
-            None => debug!("attempted to uncover a tile already uncovered: {coords}"),
+    for chord_event in tile_chord.read() {
+        if awaits_generation(&board) {
+            continue;
         }
+        let covers = board.chord(&chord_event.0);
+        uncover(
+            &mut commands,
+            &mut board,
+            &mut clock,
+            covers,
+            &mut board_completed_event_wr,
+            &mut bomb_explosion_event_wr,
+        );
+    }
+}
 
-        if board.is_completed() {
-            info!("Board completed");
-            board_completed_event_wr.send(BoardCompletedEvent);
-        }
+/// Returns true while the bombs of a `SafeStart::FirstClick` board aren't
+/// placed yet, revealing the empty map would win the game
+fn awaits_generation(board: &Board) -> bool {
+    !board.generated && matches!(board.options.safe_start, SafeStart::FirstClick { .. })
+}
 
-        if bomb.is_some() {
-            info!("Boom!");
-            // explosion event
-        } else if bomb_counter.is_none() {
-            // propogate Uncover to neighbor tiles
-            for entity in board.adjacent_covered_tiles(coords) {
-                command.entity(entity).insert(Uncover);
-            }
+/// Marks `covers` for removal and reports the end of the game
+fn uncover(
+    commands: &mut Commands,
+    board: &mut Board,
+    clock: &mut GameClock,
+    covers: Vec<Entity>,
+    board_completed_event_wr: &mut EventWriter<BoardCompletedEvent>,
+    bomb_explosion_event_wr: &mut EventWriter<BombExplosionEvent>,
+) {
+    if covers.is_empty() {
+        return;
+    }
+    clock.start();
+    for entity in covers {
+        commands.entity(entity).insert(Uncover);
+    }
+
+    match board.core.status() {
+        GameStatus::Won => {
+            clock.stop();
+            let event = completed_event(board, clock);
+            info!("Board completed, {:.2} 3BV/s", event.three_bv_per_second());
+            board_completed_event_wr.send(event);
+        }
+        GameStatus::Lost(coords) => {
+            info!("Boom! at {coords}");
+            clock.stop();
+            bomb_explosion_event_wr.send(BombExplosionEvent(coords));
         }
+        GameStatus::Playing => (),
+    }
+}
+
+/// Builds the completion event of a won `board`
+pub(crate) fn completed_event(board: &Board, clock: &GameClock) -> BoardCompletedEvent {
+    BoardCompletedEvent {
+        elapsed: clock.elapsed(),
+        clicks: board.clicks,
+        metrics: board
+            .metrics
+            .unwrap_or_else(|| BoardMetrics::new(board.tile_map(), &board.options.generation)),
+        seed: board.seed,
+    }
+}
+
+pub fn uncover_tiles(mut commands: Commands, covers: Query<Entity, With<Uncover>>) {
+    for entity in covers.iter() {
+        // destroy the tile cover
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn bomb_explosion_handler(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    board_assets: Res<BoardAssets>,
+    mut bomb_explosion_rdr: EventReader<BombExplosionEvent>,
+) {
+    if bomb_explosion_rdr.read().last().is_none() {
+        return;
+    }
+
+    for entity in board.reveal_bombs() {
+        commands.entity(entity).insert(Uncover);
+    }
+
+    // swap the flags of safe tiles for the wrong flag sprite
+    for entity in board.wrong_flags() {
+        commands.entity(entity).despawn_descendants();
+        spawn_flag(
+            &mut commands,
+            entity,
+            &board_assets.wrong_flag_material,
+            board.tile_size,
+        );
+    }
+}
+
+pub fn board_completed_handler(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    board_assets: Res<BoardAssets>,
+    mut board_completed_rdr: EventReader<BoardCompletedEvent>,
+) {
+    if board_completed_rdr.read().last().is_none() {
+        return;
+    }
+
+    for (entity, mark) in board.flag_bombs() {
+        commands.entity(entity).despawn_descendants();
+        spawn_mark(&mut commands, entity, &board_assets, mark, board.tile_size);
     }
 }
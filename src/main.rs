@@ -1,22 +1,40 @@
-use bevy::{app::AppExit, prelude::*};
+use bevy::{app::AppExit, prelude::*, utils::Duration};
 use board_plugin::{
-    resources::{BoardAssets, BoardOptions, SpriteMaterial},
-    BoardPlugin,
+    events::{
+        BoardErrorEvent, ExportStatsEvent, LoadGameEvent, PlayReplayEvent, RedoEvent,
+        ReplayControlEvent, SaveGameEvent, SaveReplayEvent, StopReplayEvent, UndoEvent,
+    },
+    resources::{
+        BoardAssets, BoardFiles, BoardOptions, DifficultySettings, Leaderboard, PlayerStats,
+        ReplayPlayer, SafeStart, SpriteMaterial,
+    },
+    BoardFilesPlugin, BoardPlugin, LeaderboardPlugin, MenuPlugin, ReplayPlugin, StatsPlugin,
 };
 
 #[cfg(feature = "debug")]
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
+const SAVE_FILE: &str = "minesweeper.sav";
+const REPLAY_FILE: &str = "minesweeper.replay";
+const STATS_EXPORT_FILE: &str = "minesweeper-stats.json";
+
+/// Replay time skipped by the seek keys
+const REPLAY_SEEK_STEP: Duration = Duration::from_secs(5);
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, States)]
 pub enum AppState {
     #[default]
+    Menu,
     Load,
     InGame,
     Paused,
+    GameOver,
+    Won,
     Out,
 }
 
 fn main() {
+    #[allow(float_literal_f32_fallback)]
     let window = WindowPlugin {
         primary_window: Some(Window {
             title: "Mine Sweeper!".to_string(),
@@ -32,11 +50,39 @@ fn main() {
     app.add_plugins(DefaultPlugins.set(window))
         .add_state::<AppState>()
         .add_systems(Startup, (setup_camera, setup_board))
-        .add_systems(Update, state_handler)
+        .add_systems(
+            Update,
+            (
+                state_handler,
+                board_controls,
+                history_controls.run_if(not(resource_exists::<ReplayPlayer>())),
+                replay_controls,
+                board_errors,
+            ),
+        )
+        .add_plugins(MenuPlugin {
+            menu_state: AppState::Menu,
+            start_state: AppState::Load,
+        })
+        .add_plugins(BoardFilesPlugin {
+            start_state: AppState::Load,
+        })
         .add_plugins(BoardPlugin {
             start_state: AppState::Load,
             running_state: AppState::InGame,
             end_state: AppState::Out,
+            lost_state: Some(AppState::GameOver),
+            won_state: Some(AppState::Won),
+        })
+        .add_plugins(ReplayPlugin {
+            start_state: AppState::Load,
+            running_state: AppState::InGame,
+        })
+        .add_plugins(LeaderboardPlugin {
+            screen_state: AppState::Out,
+        })
+        .add_plugins(StatsPlugin {
+            screen_state: AppState::Paused,
         });
 
     // Debug hiearchy inspector
@@ -51,19 +97,27 @@ fn setup_camera(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
 }
 
-fn setup_board(
-    mut commands: Commands,
-    mut next: ResMut<NextState<AppState>>,
-    asset_server: Res<AssetServer>,
-) {
+fn setup_board(mut commands: Commands, asset_server: Res<AssetServer>) {
     // options
-    commands.insert_resource(BoardOptions {
-        map_size: (20, 20),
-        bomb_count: 40,
+    let mut options = BoardOptions {
         tile_padding: 3.0,
-        safe_start: true,
+        safe_start: SafeStart::FirstClick { radius: 1 },
+        hud: true,
+        history: true,
+        question_marks: true,
         ..default()
-    });
+    };
+
+    // last difficulty picked in the menu
+    match DifficultySettings::default_path().map(DifficultySettings::open) {
+        Some(Ok(settings)) => {
+            settings.difficulty.apply(&mut options);
+            commands.insert_resource(settings);
+        }
+        Some(Err(e)) => error!("Failed to open settings: {e}"),
+        None => warn!("No data directory, difficulty won't be saved"),
+    }
+    commands.insert_resource(options);
 
     // assets
     commands.insert_resource(BoardAssets {
@@ -84,18 +138,56 @@ fn setup_board(
             color: Color::WHITE,
             texture: asset_server.load("sprites/flag.png"),
         },
+        anti_flag_material: SpriteMaterial {
+            color: Color::CYAN,
+            texture: asset_server.load("sprites/flag.png"),
+        },
+        wrong_flag_material: SpriteMaterial {
+            color: Color::WHITE,
+            texture: asset_server.load("sprites/wrong_flag.png"),
+        },
+        question_material: SpriteMaterial {
+            color: Color::WHITE,
+            texture: asset_server.load("sprites/question.png"),
+        },
         bomb_material: SpriteMaterial {
             color: Color::WHITE,
             texture: asset_server.load("sprites/bomb.png"),
         },
+        anti_bomb_material: SpriteMaterial {
+            color: Color::CYAN,
+            texture: asset_server.load("sprites/bomb.png"),
+        },
+        pointy_hex_texture: Some(asset_server.load("sprites/hex_pointy.png")),
+        flat_hex_texture: Some(asset_server.load("sprites/hex_flat.png")),
         bomb_counter_font: asset_server.load("fonts/pixeled.ttf"),
         bomb_counter_colors: BoardAssets::default_colors(),
+        negative_counter_color: Color::CYAN,
+    });
+
+    // option and theme files, replacing the options and assets above once
+    // loaded and following their edits
+    commands.insert_resource(BoardFiles {
+        options: Some(asset_server.load("boards/default.board.ron")),
+        theme: Some(asset_server.load("themes/default.theme.ron")),
     });
 
-    // activate plugin
-    next.set(AppState::InGame);
+    // leaderboard
+    match Leaderboard::default_path().map(Leaderboard::open) {
+        Some(Ok(leaderboard)) => commands.insert_resource(leaderboard),
+        Some(Err(e)) => error!("Failed to open leaderboard: {e}"),
+        None => warn!("No data directory, leaderboard disabled"),
+    }
+
+    // player stats
+    match PlayerStats::default_path().map(PlayerStats::open) {
+        Some(Ok(stats)) => commands.insert_resource(stats),
+        Some(Err(e)) => error!("Failed to open stats: {e}"),
+        None => warn!("No data directory, stats disabled"),
+    }
 }
 
+#[allow(clippy::collapsible_if)]
 fn state_handler(
     current: Res<State<AppState>>,
     mut next: ResMut<NextState<AppState>>,
@@ -103,7 +195,7 @@ fn state_handler(
     mut exit: EventWriter<AppExit>,
 ) {
     if keys.just_pressed(KeyCode::C) {
-        if current.get() != &AppState::Out {
+        if !matches!(current.get(), AppState::Out | AppState::Menu) {
             info!("clearing game");
             next.set(AppState::Out);
         }
@@ -114,6 +206,12 @@ fn state_handler(
             next.set(AppState::Load);
         }
     }
+    if keys.just_pressed(KeyCode::N) {
+        if current.get() == &AppState::Out {
+            info!("new game menu");
+            next.set(AppState::Menu);
+        }
+    }
 
     if keys.just_pressed(KeyCode::Escape)
         || keys.just_pressed(KeyCode::Pause)
@@ -139,3 +237,95 @@ fn state_handler(
         next.set(AppState::InGame);
     }
 }
+
+/// Goes back to the menu when the board options are refused
+fn board_errors(mut next: ResMut<NextState<AppState>>, mut errors: EventReader<BoardErrorEvent>) {
+    if let Some(BoardErrorEvent(e)) = errors.read().last() {
+        warn!("board not created: {e}");
+        next.set(AppState::Menu);
+    }
+}
+
+fn board_controls(
+    current: Res<State<AppState>>,
+    keys: Res<Input<KeyCode>>,
+    mut save: EventWriter<SaveGameEvent>,
+    mut export_stats: EventWriter<ExportStatsEvent>,
+) {
+    if keys.just_pressed(KeyCode::F5) && current.get() == &AppState::InGame {
+        info!("saving game");
+        save.send(SaveGameEvent(SAVE_FILE.into()));
+    }
+
+    if keys.just_pressed(KeyCode::F7) {
+        info!("exporting stats");
+        export_stats.send(ExportStatsEvent(STATS_EXPORT_FILE.into()));
+    }
+}
+
+/// Controls replacing or rewinding the board, disabled during replays
+fn history_controls(
+    current: Res<State<AppState>>,
+    keys: Res<Input<KeyCode>>,
+    mut load: EventWriter<LoadGameEvent>,
+    mut undo: EventWriter<UndoEvent>,
+    mut redo: EventWriter<RedoEvent>,
+) {
+    if keys.just_pressed(KeyCode::F9) {
+        info!("loading saved game");
+        load.send(LoadGameEvent(SAVE_FILE.into()));
+    }
+
+    if keys.just_pressed(KeyCode::Z) && current.get() != &AppState::Out {
+        info!("undo");
+        undo.send(UndoEvent);
+    }
+    if keys.just_pressed(KeyCode::Y) && current.get() == &AppState::InGame {
+        info!("redo");
+        redo.send(RedoEvent);
+    }
+}
+
+fn replay_controls(
+    keys: Res<Input<KeyCode>>,
+    player: Option<Res<ReplayPlayer>>,
+    mut save: EventWriter<SaveReplayEvent>,
+    mut play: EventWriter<PlayReplayEvent>,
+    mut stop: EventWriter<StopReplayEvent>,
+    mut control: EventWriter<ReplayControlEvent>,
+) {
+    if keys.just_pressed(KeyCode::F6) {
+        info!("saving replay");
+        save.send(SaveReplayEvent(REPLAY_FILE.into()));
+    }
+    if keys.just_pressed(KeyCode::F8) {
+        info!("playing replay");
+        play.send(PlayReplayEvent(REPLAY_FILE.into()));
+    }
+
+    let Some(player) = player else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::Back) {
+        info!("stopping replay");
+        stop.send(StopReplayEvent);
+    }
+    if keys.just_pressed(KeyCode::Space) {
+        control.send(ReplayControlEvent::TogglePause);
+    }
+    if keys.just_pressed(KeyCode::Left) {
+        let position = player.position().saturating_sub(REPLAY_SEEK_STEP);
+        control.send(ReplayControlEvent::Seek(position));
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        control.send(ReplayControlEvent::Seek(
+            player.position() + REPLAY_SEEK_STEP,
+        ));
+    }
+    if keys.just_pressed(KeyCode::Up) {
+        control.send(ReplayControlEvent::Speed(player.speed() * 2.));
+    }
+    if keys.just_pressed(KeyCode::Down) {
+        control.send(ReplayControlEvent::Speed(player.speed() / 2.));
+    }
+}